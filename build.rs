@@ -1,90 +1,480 @@
 pub use blake2b_rs::{Blake2b, Blake2bBuilder};
 use includedir_codegen::Compression;
+use serde::Deserialize;
 
 use std::{
+    collections::HashSet,
+    convert::TryInto,
     env,
-    fs::File,
-    io::{BufWriter, Read, Write},
+    fs::{self, File},
+    io::{BufWriter, Write},
     path::Path,
+    process::Command,
 };
 
 const PATH_PREFIX: &str = "specs/cells/";
-const BUF_SIZE: usize = 8 * 1024;
+const HISTORY_PATH_PREFIX: &str = "specs/history/";
+const DEBUG_PATH_PREFIX: &str = "build/";
+const MANIFEST_PATH: &str = "specs/manifest.toml";
+const VERIFY_BUILD_ENV: &str = "CKB_SYSTEM_SCRIPTS_VERIFY_BUILD";
+// Same as `TARGET` in the Makefile.
+const RISCV_TOOLCHAIN: &str = "riscv64-unknown-linux-gnu";
 const CKB_HASH_PERSONALIZATION: &[u8] = b"ckb-default-hash";
 
-const BINARIES: &[(&str, &str)] = &[
-    // (
-    //     "secp256k1_blake160_sighash_all",
-    //     "709f3fda12f561cfacf92273c57a98fede188a3f1a59b1f888d113f9cce08649",
-    // ),
-    // (
-    //     "secp256k1_data",
-    //     "9799bee251b975b82c45a02154ce28cec89c5853ecc14d12b7b8cccfc19e0af4",
-    // ),
-    // (
-    //     "dao",
-    //     "2f7e76d1a866f7a064e251bf4f2b212a28b532dd6df19a87011784bdbe69726b",
-    // ),
-    // (
-    //     "secp256k1_blake160_multisig_all",
-    //     "43400de165f0821abf63dcac299bbdf7fd73898675ee4ddb099b0a0d8db63bfb",
-    // ),
-];
+#[derive(Deserialize)]
+struct Manifest {
+    #[serde(default, rename = "script")]
+    scripts: Vec<Entry>,
+    #[serde(default, rename = "version")]
+    versions: Vec<Version>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Entry {
+    name: String,
+    path: String,
+    hash: String,
+    tag: String,
+    #[serde(default)]
+    feature: Option<String>,
+    #[serde(default = "default_elf")]
+    elf: bool,
+    #[serde(default)]
+    debug: Option<String>,
+    #[serde(default)]
+    description: Option<String>,
+    #[serde(default)]
+    source: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Version {
+    script: String,
+    tag: String,
+    path: String,
+    hash: String,
+}
+
+fn default_elf() -> bool {
+    true
+}
 
 fn main() {
+    println!("cargo:rerun-if-changed={}", MANIFEST_PATH);
+    println!("cargo:rerun-if-env-changed={}", VERIFY_BUILD_ENV);
+    let manifest = load_manifest();
+
     let mut bundled = includedir_codegen::start("BUNDLED_CELL");
 
-    let out_path = Path::new(&env::var("OUT_DIR").unwrap()).join("code_hashes.rs");
+    let out_dir = env::var("OUT_DIR").unwrap();
+    let out_path = Path::new(&out_dir).join("code_hashes.rs");
     let mut out_file = BufWriter::new(File::create(&out_path).expect("create code_hashes.rs"));
+    let elf_path = Path::new(&out_dir).join("elf_metadata.rs");
+    let mut elf_file = BufWriter::new(File::create(&elf_path).expect("create elf_metadata.rs"));
+
+    let mut history = includedir_codegen::start("BUNDLED_HISTORY");
+    let bundle_history = env::var_os("CARGO_FEATURE_HISTORY").is_some();
+    let versions_path = Path::new(&out_dir).join("script_versions.rs");
+    let mut versions_file =
+        BufWriter::new(File::create(&versions_path).expect("create script_versions.rs"));
+    writeln!(
+        &mut versions_file,
+        "const SCRIPT_VERSIONS: &[ScriptVersion] = &["
+    )
+    .expect("write to script_versions.rs");
+
+    let mut debug_symbols = if env::var_os("CARGO_FEATURE_DEBUG_SYMBOLS").is_some() {
+        let index_path = Path::new(&out_dir).join("debug_symbol_index.rs");
+        let mut index_file =
+            BufWriter::new(File::create(&index_path).expect("create debug_symbol_index.rs"));
+        writeln!(
+            &mut index_file,
+            "const DEBUG_SYMBOL_INDEX: &[([u8; 32], &str)] = &["
+        )
+        .expect("write to debug_symbol_index.rs");
+        Some((includedir_codegen::start("DEBUG_SYMBOLS"), index_file))
+    } else {
+        None
+    };
+
+    let compression = if env::var_os("CARGO_FEATURE_UNCOMPRESSED").is_some() {
+        Compression::None
+    } else {
+        Compression::Gzip
+    };
 
     let mut errors = Vec::new();
+    let mut names = HashSet::new();
+
+    for entry in &manifest.scripts {
+        if !names.insert(entry.name.as_str()) {
+            errors.push(format!("{}: duplicated entry", entry.name));
+            continue;
+        }
+        if !feature_enabled(entry.feature.as_deref()) {
+            continue;
+        }
+        let path = format!("{}{}", PATH_PREFIX, entry.path);
+        println!("cargo:rerun-if-changed={}", path);
 
-    for (name, expected_hash) in BINARIES {
-        let path = format!("{}{}", PATH_PREFIX, name);
+        let (hash, content) = match read_binary(&path, &entry.hash) {
+            Ok(result) => result,
+            Err(err) => {
+                errors.push(format!("{}: {}", entry.name, err));
+                continue;
+            }
+        };
 
-        let mut buf = [0u8; BUF_SIZE];
-        bundled
-            .add_file(&path, Compression::Gzip)
-            .expect("add files to resource bundle");
+        if entry.elf {
+            match parse_elf(&content) {
+                Ok(info) => {
+                    if let Some((debug_bundled, index_file)) = debug_symbols.as_mut() {
+                        let debug_path = entry.debug.clone().unwrap_or_else(|| {
+                            format!("{}{}.debug", DEBUG_PATH_PREFIX, entry.path)
+                        });
+                        println!("cargo:rerun-if-changed={}", debug_path);
+                        match fs::read(&debug_path) {
+                            Ok(debug_content) => {
+                                match parse_elf(&debug_content) {
+                                    Ok(debug_info)
+                                        if debug_info.entry_point == info.entry_point => {}
+                                    Ok(debug_info) => {
+                                        errors.push(format!(
+                                            "{}: {} doesn't match the binary, entry point {:#x}, expect {:#x}",
+                                            entry.name, debug_path, debug_info.entry_point, info.entry_point
+                                        ));
+                                        continue;
+                                    }
+                                    Err(err) => {
+                                        errors.push(format!(
+                                            "{}: invalid ELF {}: {}",
+                                            entry.name, debug_path, err
+                                        ));
+                                        continue;
+                                    }
+                                }
+                                debug_bundled
+                                    .add_file(&debug_path, compression)
+                                    .expect("add files to debug symbols bundle");
+                                writeln!(index_file, "({:?}, {:?}),", hash, debug_path)
+                                    .expect("write to debug_symbol_index.rs");
+                            }
+                            Err(err) => {
+                                println!(
+                                    "cargo:warning={}: debug symbols {} are not bundled: {}",
+                                    entry.name, debug_path, err
+                                );
+                            }
+                        }
+                    }
 
-        // build hash
-        let mut blake2b = new_blake2b();
-        let mut fd = File::open(&path).expect("open file");
-        loop {
-            let read_bytes = fd.read(&mut buf).expect("read file");
-            if read_bytes > 0 {
-                blake2b.update(&buf[..read_bytes]);
-            } else {
-                break;
+                    writeln!(
+                        &mut elf_file,
+                        "pub const ELF_METADATA_{}: ElfMetadata = ElfMetadata {{ text_size: {}, entry_point: {:#x}, stripped: {} }};",
+                        entry.name.to_uppercase(),
+                        info.text_size,
+                        info.entry_point,
+                        info.stripped
+                    )
+                    .expect("write to elf_metadata.rs");
+                }
+                Err(err) => {
+                    errors.push(format!("{}: invalid ELF {}: {}", entry.name, path, err));
+                    continue;
+                }
             }
         }
 
-        let mut hash = [0u8; 32];
-        blake2b.finalize(&mut hash);
+        bundled
+            .add_file(&path, compression)
+            .expect("add files to resource bundle");
 
-        let actual_hash = faster_hex::hex_string(&hash);
-        if expected_hash != &actual_hash {
-            errors.push((name, expected_hash, actual_hash));
-            continue;
+        if let Some(description) = &entry.description {
+            writeln!(&mut out_file, "/// {}", description).expect("write to code_hashes.rs");
+        }
+        if let Some(source) = &entry.source {
+            if entry.description.is_some() {
+                writeln!(&mut out_file, "///").expect("write to code_hashes.rs");
+            }
+            writeln!(&mut out_file, "/// Source: `{}`", source).expect("write to code_hashes.rs");
         }
-
         writeln!(
             &mut out_file,
             "pub const CODE_HASH_{}: [u8; 32] = {:?};",
-            name.to_uppercase(),
+            entry.name.to_uppercase(),
             hash
         )
         .expect("write to code_hashes.rs");
+        write_version(
+            &mut versions_file,
+            &entry.name,
+            &entry.tag,
+            &hash,
+            Some(&path),
+        );
+    }
+
+    for version in &manifest.versions {
+        let entry = match manifest.scripts.iter().find(|e| e.name == version.script) {
+            Some(entry) => entry,
+            None => {
+                errors.push(format!(
+                    "{} {}: unknown script",
+                    version.script, version.tag
+                ));
+                continue;
+            }
+        };
+        if !feature_enabled(entry.feature.as_deref()) {
+            continue;
+        }
+        if manifest
+            .versions
+            .iter()
+            .filter(|v| v.script == version.script && v.tag == version.tag)
+            .count()
+            > 1
+        {
+            errors.push(format!(
+                "{} {}: duplicated version",
+                version.script, version.tag
+            ));
+            continue;
+        }
+        // Historical versions are older builds than the current one.
+        match (parse_tag(&version.tag), parse_tag(&entry.tag)) {
+            (Ok(tag), Ok(current_tag)) if tag < current_tag => {}
+            (Ok(_), Ok(_)) => {
+                errors.push(format!(
+                    "{} {}: not older than the current {}",
+                    version.script, version.tag, entry.tag
+                ));
+                continue;
+            }
+            (Err(err), _) | (_, Err(err)) => {
+                errors.push(format!("{} {}: {}", version.script, version.tag, err));
+                continue;
+            }
+        }
+
+        if !bundle_history {
+            match parse_hash(&version.hash) {
+                Ok(hash) => write_version(
+                    &mut versions_file,
+                    &version.script,
+                    &version.tag,
+                    &hash,
+                    None,
+                ),
+                Err(err) => errors.push(format!("{} {}: {}", version.script, version.tag, err)),
+            }
+            continue;
+        }
+
+        let path = format!("{}{}", HISTORY_PATH_PREFIX, version.path);
+        println!("cargo:rerun-if-changed={}", path);
+        match read_binary(&path, &version.hash) {
+            Ok((hash, _)) => {
+                history
+                    .add_file(&path, compression)
+                    .expect("add files to history bundle");
+                write_version(
+                    &mut versions_file,
+                    &version.script,
+                    &version.tag,
+                    &hash,
+                    Some(&path),
+                );
+            }
+            Err(err) => errors.push(format!("{} {}: {}", version.script, version.tag, err)),
+        }
+    }
+    writeln!(&mut versions_file, "];").expect("write to script_versions.rs");
+
+    if matches!(env::var_os(VERIFY_BUILD_ENV), Some(v) if !v.is_empty() && v != "0") {
+        verify_build(&manifest, Path::new(&out_dir), &mut errors);
     }
 
     if !errors.is_empty() {
-        for (name, expected, actual) in errors.into_iter() {
-            eprintln!("{}: expect {}, actual {}", name, expected, actual);
+        for error in errors.into_iter() {
+            eprintln!("{}", error);
         }
-        panic!("not all hashes are right");
+        panic!("not all entries in {} are right", MANIFEST_PATH);
     }
 
     bundled.build("bundled.rs").expect("build resource bundle");
+    history.build("history.rs").expect("build history bundle");
+    if let Some((debug_bundled, mut index_file)) = debug_symbols {
+        writeln!(&mut index_file, "];").expect("write to debug_symbol_index.rs");
+        debug_bundled
+            .build("debug_symbols.rs")
+            .expect("build debug symbols bundle");
+    }
+}
+
+fn parse_hash(hex: &str) -> Result<[u8; 32], String> {
+    let mut hash = [0u8; 32];
+    if hex.len() != 64 || faster_hex::hex_decode(hex.as_bytes(), &mut hash).is_err() {
+        return Err(format!("invalid hash {:?}, expect 32 bytes in hex", hex));
+    }
+    Ok(hash)
+}
+
+/// Parses a release tag like `v0.5.1` into its version numbers.
+fn parse_tag(tag: &str) -> Result<(u64, u64, u64), String> {
+    let invalid = || format!("invalid tag {:?}, expect vMAJOR.MINOR.PATCH", tag);
+    let mut numbers = tag
+        .strip_prefix('v')
+        .ok_or_else(invalid)?
+        .split('.')
+        .map(|number| number.parse::<u64>().map_err(|_| invalid()));
+    match (
+        numbers.next(),
+        numbers.next(),
+        numbers.next(),
+        numbers.next(),
+    ) {
+        (Some(major), Some(minor), Some(patch), None) => Ok((major?, minor?, patch?)),
+        _ => Err(invalid()),
+    }
+}
+
+/// Reads the binary at `path` and checks its blake2b hash against `expected_hash`.
+fn read_binary(path: &str, expected_hash: &str) -> Result<([u8; 32], Vec<u8>), String> {
+    let expected_hash = parse_hash(expected_hash)?;
+    let content = fs::read(path).map_err(|err| format!("open {}: {}", path, err))?;
+
+    // build hash
+    let mut blake2b = new_blake2b();
+    blake2b.update(&content);
+    let mut hash = [0u8; 32];
+    blake2b.finalize(&mut hash);
+
+    if expected_hash != hash {
+        return Err(format!(
+            "expect {}, actual {}",
+            faster_hex::hex_string(&expected_hash),
+            faster_hex::hex_string(&hash)
+        ));
+    }
+    Ok((hash, content))
+}
+
+fn write_version<W: Write>(
+    out: &mut W,
+    name: &str,
+    tag: &str,
+    hash: &[u8; 32],
+    path: Option<&str>,
+) {
+    writeln!(
+        out,
+        "ScriptVersion {{ name: {:?}, tag: {:?}, data_hash: {:?}, path: {:?} }},",
+        name, tag, hash, path
+    )
+    .expect("write to script_versions.rs");
+}
+
+/// Rebuilds the binaries from `c/` with the Makefile in `OUT_DIR` and checks they are identical
+/// to the bundled ones.
+fn verify_build(manifest: &Manifest, out_dir: &Path, errors: &mut Vec<String>) {
+    println!("cargo:rerun-if-env-changed=PATH");
+    let gcc = format!("{}-gcc", RISCV_TOOLCHAIN);
+    if Command::new(&gcc).arg("--version").output().is_err() {
+        println!(
+            "cargo:warning=skip verifying the binaries: {} is not found, install the RISC-V toolchain or run `make all-via-docker`",
+            gcc
+        );
+        return;
+    }
+    println!("cargo:rerun-if-changed=c");
+
+    let build_dir = out_dir.join("verify-build");
+    let _ = fs::remove_dir_all(&build_dir);
+    for path in &["c", "deps", "Makefile"] {
+        copy_all(Path::new(path), &build_dir.join(path)).expect("copy sources to verify");
+    }
+    fs::create_dir_all(build_dir.join("build")).expect("create build dir");
+    fs::create_dir_all(build_dir.join(PATH_PREFIX)).expect("create cells dir");
+
+    let output = Command::new("make")
+        .arg("all")
+        .current_dir(&build_dir)
+        .output()
+        .expect("run make");
+    if !output.status.success() {
+        errors.push(format!(
+            "verify build: make failed in {}:\n{}",
+            build_dir.display(),
+            String::from_utf8_lossy(&output.stderr)
+        ));
+        return;
+    }
+
+    for entry in &manifest.scripts {
+        if !feature_enabled(entry.feature.as_deref()) {
+            continue;
+        }
+        let path = format!("{}{}", PATH_PREFIX, entry.path);
+        let built = match fs::read(build_dir.join(&path)) {
+            Ok(content) => content,
+            Err(_) => {
+                println!(
+                    "cargo:warning={}: not built by `make all`, skip verifying",
+                    entry.name
+                );
+                continue;
+            }
+        };
+        match read_binary(&path, &entry.hash) {
+            Ok((_, content)) if content == built => {}
+            Ok(_) => {
+                let mut blake2b = new_blake2b();
+                blake2b.update(&built);
+                let mut hash = [0u8; 32];
+                blake2b.finalize(&mut hash);
+                errors.push(format!(
+                    "{}: not reproducible, {} is built from source with hash {}",
+                    entry.name,
+                    path,
+                    faster_hex::hex_string(&hash)
+                ));
+            }
+            // reported when bundling
+            Err(_) => {}
+        }
+    }
+}
+
+fn copy_all(from: &Path, to: &Path) -> std::io::Result<()> {
+    if from.is_dir() {
+        fs::create_dir_all(to)?;
+        for entry in fs::read_dir(from)? {
+            let entry = entry?;
+            copy_all(&entry.path(), &to.join(entry.file_name()))?;
+        }
+    } else {
+        fs::copy(from, to)?;
+    }
+    Ok(())
+}
+
+fn feature_enabled(feature: Option<&str>) -> bool {
+    match feature {
+        Some(feature) => {
+            let var = format!("CARGO_FEATURE_{}", feature.to_uppercase().replace('-', "_"));
+            env::var_os(var).is_some()
+        }
+        None => true,
+    }
+}
+
+fn load_manifest() -> Manifest {
+    let content = fs::read_to_string(MANIFEST_PATH)
+        .unwrap_or_else(|err| panic!("read {}: {}", MANIFEST_PATH, err));
+    toml::from_str(&content).unwrap_or_else(|err| panic!("parse {}: {}", MANIFEST_PATH, err))
 }
 
 pub fn new_blake2b() -> Blake2b {
@@ -92,3 +482,147 @@ pub fn new_blake2b() -> Blake2b {
         .personal(CKB_HASH_PERSONALIZATION)
         .build()
 }
+
+// CKB VM only runs statically linked, 64-bit little endian RISC-V executables.
+const ELF_MAGIC: &[u8] = b"\x7fELF";
+const ELFCLASS64: u8 = 2;
+const ELFDATA2LSB: u8 = 1;
+const EM_RISCV: u16 = 243;
+const PT_LOAD: u32 = 1;
+const PT_INTERP: u32 = 3;
+const PF_X: u32 = 1;
+const SHT_SYMTAB: u32 = 2;
+const SHF_EXECINSTR: u64 = 4;
+
+struct ElfInfo {
+    text_size: u64,
+    entry_point: u64,
+    stripped: bool,
+}
+
+fn parse_elf(data: &[u8]) -> Result<ElfInfo, String> {
+    if data.len() < 64 || &data[..4] != ELF_MAGIC {
+        return Err("bad magic".to_string());
+    }
+    if data[4] != ELFCLASS64 {
+        return Err(format!("class is {}, expect 64-bit", data[4]));
+    }
+    if data[5] != ELFDATA2LSB {
+        return Err("not little endian".to_string());
+    }
+    let machine = read_u16(data, 18)?;
+    if machine != EM_RISCV {
+        return Err(format!("machine type is {}, expect RISC-V", machine));
+    }
+    let entry_point = read_u64(data, 24)?;
+    let phoff = read_u64(data, 32)? as usize;
+    let shoff = read_u64(data, 40)? as usize;
+    let phentsize = read_u16(data, 54)? as usize;
+    let phnum = read_u16(data, 56)? as usize;
+    let shentsize = read_u16(data, 58)? as usize;
+    let shnum = read_u16(data, 60)? as usize;
+    let shstrndx = read_u16(data, 62)? as usize;
+
+    let mut segments_text_size = 0u64;
+    for i in 0..phnum {
+        let p_type = read_u32(data, entry_offset(phoff, i, phentsize, 0)?)?;
+        if p_type == PT_INTERP {
+            return Err("has an interpreter".to_string());
+        }
+        let p_flags = read_u32(data, entry_offset(phoff, i, phentsize, 4)?)?;
+        if p_type == PT_LOAD && p_flags & PF_X != 0 {
+            let p_filesz = read_u64(data, entry_offset(phoff, i, phentsize, 32)?)?;
+            segments_text_size = add_size(segments_text_size, p_filesz)?;
+        }
+    }
+
+    // Section headers are optional, fall back to executable segments without them.
+    if shnum == 0 {
+        return Ok(ElfInfo {
+            text_size: segments_text_size,
+            entry_point,
+            stripped: true,
+        });
+    }
+    if shstrndx >= shnum {
+        return Err(format!("invalid section name table index {}", shstrndx));
+    }
+    let strtab_offset = read_u64(data, entry_offset(shoff, shstrndx, shentsize, 24)?)? as usize;
+    let mut text_size = 0u64;
+    let mut stripped = true;
+    for i in 0..shnum {
+        let sh_name = read_u32(data, entry_offset(shoff, i, shentsize, 0)?)? as usize;
+        let name_offset = strtab_offset
+            .checked_add(sh_name)
+            .ok_or_else(|| format!("section name offset overflows at section {}", i))?;
+        let name = read_str(data, name_offset)?;
+        let sh_type = read_u32(data, entry_offset(shoff, i, shentsize, 4)?)?;
+        let sh_flags = read_u64(data, entry_offset(shoff, i, shentsize, 8)?)?;
+        if sh_flags & SHF_EXECINSTR != 0 {
+            let sh_size = read_u64(data, entry_offset(shoff, i, shentsize, 32)?)?;
+            text_size = add_size(text_size, sh_size)?;
+        }
+        if sh_type == SHT_SYMTAB || name.starts_with(b".debug") {
+            stripped = false;
+        }
+    }
+    Ok(ElfInfo {
+        text_size,
+        entry_point,
+        stripped,
+    })
+}
+
+/// Offset of `field` in entry `index` of the header table at `table`, the values come from the
+/// file so the arithmetic is checked.
+fn entry_offset(
+    table: usize,
+    index: usize,
+    entry_size: usize,
+    field: usize,
+) -> Result<usize, String> {
+    index
+        .checked_mul(entry_size)
+        .and_then(|offset| offset.checked_add(table))
+        .and_then(|offset| offset.checked_add(field))
+        .ok_or_else(|| format!("header table offset overflows at entry {}", index))
+}
+
+fn add_size(total: u64, size: u64) -> Result<u64, String> {
+    total
+        .checked_add(size)
+        .ok_or_else(|| "executable size overflows".to_string())
+}
+
+fn read_bytes(data: &[u8], offset: usize, len: usize) -> Result<&[u8], String> {
+    offset
+        .checked_add(len)
+        .and_then(|end| data.get(offset..end))
+        .ok_or_else(|| format!("truncated at offset {}", offset))
+}
+
+fn read_u16(data: &[u8], offset: usize) -> Result<u16, String> {
+    let bytes = read_bytes(data, offset, 2)?;
+    Ok(u16::from_le_bytes(bytes.try_into().unwrap()))
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Result<u32, String> {
+    let bytes = read_bytes(data, offset, 4)?;
+    Ok(u32::from_le_bytes(bytes.try_into().unwrap()))
+}
+
+fn read_u64(data: &[u8], offset: usize) -> Result<u64, String> {
+    let bytes = read_bytes(data, offset, 8)?;
+    Ok(u64::from_le_bytes(bytes.try_into().unwrap()))
+}
+
+fn read_str(data: &[u8], offset: usize) -> Result<&[u8], String> {
+    let rest = data
+        .get(offset..)
+        .ok_or_else(|| format!("truncated at offset {}", offset))?;
+    let end = rest
+        .iter()
+        .position(|b| *b == 0)
+        .ok_or_else(|| format!("unterminated string at offset {}", offset))?;
+    Ok(&rest[..end])
+}
@@ -0,0 +1,300 @@
+//! Human readable addresses of lock scripts, following RFC 0021.
+//!
+//! The full format encodes any lock script with bech32m:
+//!
+//! ```text
+//! 0x00 | code_hash | hash_type | args
+//! ```
+//!
+//! The deprecated short format encodes a `secp256k1_blake160_sighash_all` or
+//! `secp256k1_blake160_multisig_all` lock referenced by its genesis type hash, with 20 bytes
+//! args, with bech32:
+//!
+//! ```text
+//! 0x01 | code_index | args
+//! ```
+//!
+//! Mainnet addresses start with `ckb` and testnet ones with `ckt`.
+
+use crate::type_id::{
+    TYPE_HASH_SECP256K1_BLAKE160_MULTISIG_ALL, TYPE_HASH_SECP256K1_BLAKE160_SIGHASH_ALL,
+};
+use bech32::{FromBase32, ToBase32, Variant};
+use ckb_types::{
+    bytes::Bytes,
+    core::ScriptHashType,
+    packed::{Byte32, Script},
+    prelude::*,
+    H256,
+};
+use std::{convert::TryFrom, error, fmt, str::FromStr};
+
+const FORMAT_FULL: u8 = 0x00;
+const FORMAT_SHORT: u8 = 0x01;
+const CODE_HASH_SIZE: usize = 32;
+const SHORT_ARGS_SIZE: usize = 20;
+
+/// The network an address belongs to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Network {
+    /// Mainnet, prefixed with `ckb`.
+    Mainnet,
+    /// Testnet, prefixed with `ckt`.
+    Testnet,
+}
+
+impl Network {
+    /// The human readable part of the addresses.
+    pub fn prefix(self) -> &'static str {
+        match self {
+            Network::Mainnet => "ckb",
+            Network::Testnet => "ckt",
+        }
+    }
+
+    /// The network of the human readable part `prefix`.
+    pub fn from_prefix(prefix: &str) -> Option<Self> {
+        match prefix {
+            "ckb" => Some(Network::Mainnet),
+            "ckt" => Some(Network::Testnet),
+            _ => None,
+        }
+    }
+}
+
+/// Locks of the short format.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum CodeIndex {
+    /// `secp256k1_blake160_sighash_all`, args are the blake160 hash of the pubkey.
+    Sighash = 0x00,
+    /// `secp256k1_blake160_multisig_all`, args are the blake160 hash of the multisig script.
+    Multisig = 0x01,
+}
+
+impl CodeIndex {
+    /// The code index of `code_hash`, which is the genesis type hash of the lock.
+    pub fn from_code_hash(code_hash: &[u8; 32]) -> Option<Self> {
+        if code_hash == &TYPE_HASH_SECP256K1_BLAKE160_SIGHASH_ALL {
+            Some(CodeIndex::Sighash)
+        } else if code_hash == &TYPE_HASH_SECP256K1_BLAKE160_MULTISIG_ALL {
+            Some(CodeIndex::Multisig)
+        } else {
+            None
+        }
+    }
+
+    /// The genesis type hash of the lock, referenced with `hash_type: type`.
+    pub fn code_hash(self) -> [u8; 32] {
+        match self {
+            CodeIndex::Sighash => TYPE_HASH_SECP256K1_BLAKE160_SIGHASH_ALL,
+            CodeIndex::Multisig => TYPE_HASH_SECP256K1_BLAKE160_MULTISIG_ALL,
+        }
+    }
+
+    fn from_u8(index: u8) -> Option<Self> {
+        match index {
+            0x00 => Some(CodeIndex::Sighash),
+            0x01 => Some(CodeIndex::Multisig),
+            _ => None,
+        }
+    }
+}
+
+/// The lock script encoded in an address.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AddressPayload {
+    /// Full format of any lock script.
+    Full(Script),
+    /// Short format of a sighash or multisig lock.
+    Short {
+        /// The lock.
+        code_index: CodeIndex,
+        /// The lock args.
+        args: [u8; SHORT_ARGS_SIZE],
+    },
+}
+
+impl AddressPayload {
+    /// The lock script.
+    pub fn script(&self) -> Script {
+        match self {
+            AddressPayload::Full(script) => script.clone(),
+            AddressPayload::Short { code_index, args } => Script::new_builder()
+                .code_hash(code_index.code_hash().pack())
+                .hash_type(ScriptHashType::Type.into())
+                .args(Bytes::from(args.to_vec()).pack())
+                .build(),
+        }
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        match self {
+            AddressPayload::Full(script) => {
+                let mut bytes = vec![FORMAT_FULL];
+                bytes.extend_from_slice(script.code_hash().as_slice());
+                bytes.extend_from_slice(script.hash_type().as_slice());
+                bytes.extend_from_slice(&script.args().raw_data());
+                bytes
+            }
+            AddressPayload::Short { code_index, args } => {
+                let mut bytes = vec![FORMAT_SHORT, *code_index as u8];
+                bytes.extend_from_slice(args);
+                bytes
+            }
+        }
+    }
+
+    fn variant(&self) -> Variant {
+        match self {
+            AddressPayload::Full(_) => Variant::Bech32m,
+            AddressPayload::Short { .. } => Variant::Bech32,
+        }
+    }
+}
+
+/// Errors when parsing an address.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AddressError {
+    /// The address is not a valid bech32 or bech32m string.
+    InvalidEncoding,
+    /// The human readable part is neither `ckb` nor `ckt`.
+    UnknownPrefix(String),
+    /// The format byte is not supported.
+    UnknownFormat(u8),
+    /// The format is encoded with the wrong checksum variant.
+    InvalidVariant,
+    /// The payload is too short, or a short format payload is not 22 bytes.
+    InvalidLength(usize),
+    /// The code index of the short format is unknown.
+    UnknownCodeIndex(u8),
+    /// The hash type of the full format is unknown.
+    UnknownHashType(u8),
+}
+
+impl fmt::Display for AddressError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AddressError::InvalidEncoding => write!(f, "invalid bech32 encoding"),
+            AddressError::UnknownPrefix(prefix) => write!(f, "unknown prefix {}", prefix),
+            AddressError::UnknownFormat(format) => write!(f, "unknown format {:#04x}", format),
+            AddressError::InvalidVariant => write!(f, "invalid checksum variant for the format"),
+            AddressError::InvalidLength(len) => write!(f, "invalid payload length {}", len),
+            AddressError::UnknownCodeIndex(index) => {
+                write!(f, "unknown code index {:#04x}", index)
+            }
+            AddressError::UnknownHashType(hash_type) => {
+                write!(f, "unknown hash type {:#04x}", hash_type)
+            }
+        }
+    }
+}
+
+impl error::Error for AddressError {}
+
+/// An address of a lock script.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Address {
+    /// The network of the address.
+    pub network: Network,
+    /// The lock script.
+    pub payload: AddressPayload,
+}
+
+impl Address {
+    /// The full format address of `lock`.
+    pub fn new(network: Network, lock: Script) -> Self {
+        Address {
+            network,
+            payload: AddressPayload::Full(lock),
+        }
+    }
+
+    /// The short format address of `lock`, `None` unless it is a sighash or multisig lock
+    /// referenced by type hash with 20 bytes args.
+    pub fn new_short(network: Network, lock: &Script) -> Option<Self> {
+        let code_hash: H256 = lock.code_hash().unpack();
+        let code_index = CodeIndex::from_code_hash(&code_hash.0)?;
+        let args = lock.args().raw_data();
+        if lock.hash_type() != ScriptHashType::Type.into() || args.len() != SHORT_ARGS_SIZE {
+            return None;
+        }
+        let mut short_args = [0u8; SHORT_ARGS_SIZE];
+        short_args.copy_from_slice(&args);
+        Some(Address {
+            network,
+            payload: AddressPayload::Short {
+                code_index,
+                args: short_args,
+            },
+        })
+    }
+
+    /// The lock script.
+    pub fn script(&self) -> Script {
+        self.payload.script()
+    }
+}
+
+impl fmt::Display for Address {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let address = bech32::encode(
+            self.network.prefix(),
+            self.payload.to_bytes().to_base32(),
+            self.payload.variant(),
+        )
+        .map_err(|_| fmt::Error)?;
+        write!(f, "{}", address)
+    }
+}
+
+impl FromStr for Address {
+    type Err = AddressError;
+
+    fn from_str(address: &str) -> Result<Self, Self::Err> {
+        let (prefix, data, variant) =
+            bech32::decode(address).map_err(|_| AddressError::InvalidEncoding)?;
+        let network = match Network::from_prefix(&prefix) {
+            Some(network) => network,
+            None => return Err(AddressError::UnknownPrefix(prefix)),
+        };
+        let data = Vec::<u8>::from_base32(&data).map_err(|_| AddressError::InvalidEncoding)?;
+        let format = *data.first().ok_or(AddressError::InvalidLength(0))?;
+        let payload = match format {
+            FORMAT_FULL => {
+                if variant != Variant::Bech32m {
+                    return Err(AddressError::InvalidVariant);
+                }
+                if data.len() < 2 + CODE_HASH_SIZE {
+                    return Err(AddressError::InvalidLength(data.len()));
+                }
+                let hash_type = data[1 + CODE_HASH_SIZE];
+                ScriptHashType::try_from(hash_type)
+                    .map_err(|_| AddressError::UnknownHashType(hash_type))?;
+                AddressPayload::Full(
+                    Script::new_builder()
+                        .code_hash(Byte32::new_unchecked(Bytes::from(
+                            data[1..=CODE_HASH_SIZE].to_vec(),
+                        )))
+                        .hash_type(hash_type.into())
+                        .args(Bytes::from(data[2 + CODE_HASH_SIZE..].to_vec()).pack())
+                        .build(),
+                )
+            }
+            FORMAT_SHORT => {
+                if variant != Variant::Bech32 {
+                    return Err(AddressError::InvalidVariant);
+                }
+                if data.len() != 2 + SHORT_ARGS_SIZE {
+                    return Err(AddressError::InvalidLength(data.len()));
+                }
+                let code_index =
+                    CodeIndex::from_u8(data[1]).ok_or(AddressError::UnknownCodeIndex(data[1]))?;
+                let mut args = [0u8; SHORT_ARGS_SIZE];
+                args.copy_from_slice(&data[2..]);
+                AddressPayload::Short { code_index, args }
+            }
+            _ => return Err(AddressError::UnknownFormat(format)),
+        };
+        Ok(Address { network, payload })
+    }
+}
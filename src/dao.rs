@@ -0,0 +1,297 @@
+//! Helpers for the NervosDAO type script.
+//!
+//! A deposited cell is withdrawn in two phases. Phase 1 turns it into a withdrawing cell which
+//! stores the deposit block number, phase 2 spends the withdrawing cell with the compensation
+//! accumulated since the deposit, following the accumulate rates of the block headers.
+//!
+//! The withdrawing input of phase 2 must have an absolute epoch `since`, no earlier than whole
+//! lock periods after the deposit, see [`minimal_withdraw_since`].
+//!
+//! [`deposit`], [`withdraw_phase1`] and [`withdraw_phase2`] add the DAO cells of each step to an
+//! unsigned transaction. The cell deps, the other inputs and outputs, and the signatures are
+//! left to the caller.
+
+use crate::{
+    script_error::DaoError,
+    sighash::InputWitnesses,
+    since::{EpochNumberWithFraction, Since},
+};
+use ckb_types::{
+    bytes::Bytes,
+    core::{Capacity, HeaderView, TransactionView},
+    packed::{Byte32, CellInput, CellOutput, OutPoint, Script},
+    prelude::*,
+};
+use std::{cmp::Ordering, convert::TryInto, error, fmt};
+
+/// One lock period of 180 epochs, roughly 30 days.
+pub const LOCK_PERIOD_EPOCHS: u64 = 180;
+/// Size of the data of a DAO cell, zero for a deposited cell and the deposit block number for
+/// a withdrawing cell.
+pub const DAO_DATA_SIZE: usize = 8;
+
+/// Errors when adding the DAO cells to a transaction.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DaoBuildError {
+    /// The inputs and outputs of the transaction don't pair up, so the withdrawing cell would
+    /// not be at the index of the deposited cell.
+    UnpairedInputsOutputs,
+    /// The transaction doesn't have one output data per output.
+    UnpairedOutputsData,
+    /// The deposit header is at this index of the header deps, beyond the first 256.
+    HeaderDepIndexTooLarge(usize),
+    /// The existing witness of the input at this index is not a `WitnessArgs`.
+    InvalidWitnessArgs(usize),
+    /// The headers are rejected like the script would, see [`minimal_withdraw_since`].
+    Dao(DaoError),
+}
+
+impl fmt::Display for DaoBuildError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DaoBuildError::UnpairedInputsOutputs => write!(f, "inputs and outputs don't pair up"),
+            DaoBuildError::UnpairedOutputsData => {
+                write!(f, "outputs data count mismatches outputs count")
+            }
+            DaoBuildError::HeaderDepIndexTooLarge(index) => {
+                write!(f, "deposit header dep index {} is larger than 255", index)
+            }
+            DaoBuildError::InvalidWitnessArgs(index) => {
+                write!(f, "witness of input {} is not a WitnessArgs", index)
+            }
+            DaoBuildError::Dao(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl error::Error for DaoBuildError {}
+
+impl From<DaoError> for DaoBuildError {
+    fn from(err: DaoError) -> Self {
+        DaoBuildError::Dao(err)
+    }
+}
+
+/// Adds an output depositing `capacity` shannons locked by `lock`.
+///
+/// `dao_type` is the NervosDAO type script, with empty args.
+pub fn deposit(
+    tx: &TransactionView,
+    dao_type: Script,
+    lock: Script,
+    capacity: u64,
+) -> TransactionView {
+    let output = CellOutput::new_builder()
+        .capacity(capacity.pack())
+        .lock(lock)
+        .type_(Some(dao_type).pack())
+        .build();
+    tx.as_advanced_builder()
+        .output(output)
+        .output_data(Bytes::from(vec![0; DAO_DATA_SIZE]).pack())
+        .build()
+}
+
+/// Adds the deposited cell at `out_point` as an input, and the withdrawing cell replacing it as
+/// the output at the same index.
+///
+/// `deposited` is the deposited cell output, which the withdrawing cell copies, and
+/// `deposit_header` the header of the block committing it, added to the header deps.
+///
+/// The script only looks for the withdrawing cell at the index of the deposited cell, so the
+/// inputs and outputs of `tx` must pair up, otherwise the error is `UnpairedInputsOutputs`.
+/// The data of the withdrawing cell is appended to the outputs data, which must pair up with
+/// the outputs too.
+pub fn withdraw_phase1(
+    tx: &TransactionView,
+    out_point: OutPoint,
+    deposited: CellOutput,
+    deposit_header: &HeaderView,
+) -> Result<TransactionView, DaoBuildError> {
+    if tx.inputs().len() != tx.outputs().len() {
+        return Err(DaoBuildError::UnpairedInputsOutputs);
+    }
+    if tx.outputs_data().len() != tx.outputs().len() {
+        return Err(DaoBuildError::UnpairedOutputsData);
+    }
+    let mut header_deps: Vec<Byte32> = tx.header_deps().into_iter().collect();
+    add_header_dep(&mut header_deps, deposit_header);
+    Ok(tx
+        .as_advanced_builder()
+        .input(CellInput::new(out_point, 0))
+        .output(deposited)
+        .output_data(Bytes::from(deposit_header.number().to_le_bytes().to_vec()).pack())
+        .set_header_deps(header_deps)
+        .build())
+}
+
+/// Adds the withdrawing cell at `out_point` as an input with the [`minimal_withdraw_since`].
+///
+/// Both headers are added to the header deps, `withdraw_header` being the one committing the
+/// withdrawing cell, and the index of `deposit_header` is written to the `input_type` of the
+/// witness of the input. Missing witnesses are filled with empty ones first. The outputs
+/// receiving at most [`calculate_maximum_withdraw`] are left to the caller.
+///
+/// The script reads only the first byte of the index, so the deposit header must be one of
+/// the first 256 header deps, otherwise the error is `HeaderDepIndexTooLarge`. An existing
+/// witness of the input which is not a `WitnessArgs` fails with `InvalidWitnessArgs`.
+pub fn withdraw_phase2(
+    tx: &TransactionView,
+    out_point: OutPoint,
+    deposit_header: &HeaderView,
+    withdraw_header: &HeaderView,
+) -> Result<TransactionView, DaoBuildError> {
+    let since = Since::absolute_epoch(minimal_withdraw_since(deposit_header, withdraw_header)?);
+    let mut header_deps: Vec<Byte32> = tx.header_deps().into_iter().collect();
+    let deposit_index = add_header_dep(&mut header_deps, deposit_header);
+    if deposit_index > usize::from(u8::MAX) {
+        return Err(DaoBuildError::HeaderDepIndexTooLarge(deposit_index));
+    }
+    add_header_dep(&mut header_deps, withdraw_header);
+
+    let input_index = tx.inputs().len();
+    let mut witnesses = InputWitnesses::new(tx, input_index + 1);
+    let input_type = Bytes::from((deposit_index as u64).to_le_bytes().to_vec());
+    witnesses
+        .update_args(input_index, |builder| {
+            builder.input_type(Some(input_type).pack())
+        })
+        .map_err(|_| DaoBuildError::InvalidWitnessArgs(input_index))?;
+
+    Ok(witnesses
+        .set_to(tx)
+        .as_advanced_builder()
+        .input(CellInput::new(out_point, since.as_u64()))
+        .set_header_deps(header_deps)
+        .build())
+}
+
+/// The maximum capacity in shannons that phase 2 can withdraw from a cell, like
+/// `calculate_dao_input_capacity` of `c/dao.c`.
+///
+/// `output` and `output_data` are the withdrawing cell, or equally the deposited cell which
+/// has the same capacity and occupied capacity. `deposit_header` is the header of the block
+/// committing the deposited cell and `withdraw_header` the one committing the withdrawing
+/// cell. Only the occupied capacity is excluded from the compensation.
+pub fn calculate_maximum_withdraw(
+    deposit_header: &HeaderView,
+    withdraw_header: &HeaderView,
+    output: &CellOutput,
+    output_data: &[u8],
+) -> Result<u64, DaoError> {
+    let occupied_capacity = Capacity::bytes(output_data.len())
+        .and_then(|data_capacity| output.occupied_capacity(data_capacity))
+        .map_err(|_| DaoError::Overflow)?;
+    maximum_withdraw(
+        output.capacity().unpack(),
+        occupied_capacity.as_u64(),
+        accumulate_rate(deposit_header),
+        accumulate_rate(withdraw_header),
+    )
+}
+
+/// The earliest epoch of the `since` of the withdrawing input in phase 2.
+///
+/// It is the deposit epoch plus the deposited epochs rounded up to whole lock periods, at the
+/// fraction of the deposit epoch, and is set with `Since::absolute_epoch`. The headers are
+/// the ones of the blocks committing the deposited and the withdrawing cells, the withdraw
+/// must be after the deposit.
+pub fn minimal_withdraw_since(
+    deposit_header: &HeaderView,
+    withdraw_header: &HeaderView,
+) -> Result<EpochNumberWithFraction, DaoError> {
+    let deposit = header_epoch(deposit_header)?;
+    let withdraw = header_epoch(withdraw_header)?;
+    let number = deposit.number() + lock_epochs(deposit, withdraw)?;
+    EpochNumberWithFraction::new(number, deposit.index(), deposit.length())
+        .ok_or(DaoError::Overflow)
+}
+
+/// Whether the withdrawing cell can be spent in a block of `tip_epoch`, the epoch of the block
+/// committing the phase 2 transaction.
+pub fn is_withdrawable(
+    deposit_header: &HeaderView,
+    withdraw_header: &HeaderView,
+    tip_epoch: EpochNumberWithFraction,
+) -> Result<bool, DaoError> {
+    let minimal_since = minimal_withdraw_since(deposit_header, withdraw_header)?;
+    Ok(tip_epoch.cmp_epoch(minimal_since) != Ordering::Less)
+}
+
+/// The epoch of a header, which must have a non-zero length.
+pub(crate) fn header_epoch(header: &HeaderView) -> Result<EpochNumberWithFraction, DaoError> {
+    let epoch = EpochNumberWithFraction::from_full_value(header.epoch().full_value());
+    if epoch.length() == 0 || epoch.index() >= epoch.length() {
+        return Err(DaoError::IncorrectEpoch);
+    }
+    Ok(epoch)
+}
+
+/// The epochs between the deposit and the withdraw, rounded up to whole lock periods.
+pub(crate) fn lock_epochs(
+    deposit: EpochNumberWithFraction,
+    withdraw: EpochNumberWithFraction,
+) -> Result<u64, DaoError> {
+    let withdraw_fraction = withdraw.index() * deposit.length();
+    let deposit_fraction = deposit.index() * withdraw.length();
+    if withdraw.number() < deposit.number()
+        || (withdraw.number() == deposit.number() && withdraw_fraction <= deposit_fraction)
+    {
+        return Err(DaoError::InvalidWithdrawBlock);
+    }
+    let mut deposited_epochs = withdraw.number() - deposit.number();
+    if withdraw_fraction > deposit_fraction {
+        deposited_epochs += 1;
+    }
+    let lock_epochs = match deposited_epochs % LOCK_PERIOD_EPOCHS {
+        0 => deposited_epochs,
+        rest => deposited_epochs + (LOCK_PERIOD_EPOCHS - rest),
+    };
+    // Unreachable once the withdraw is after the deposit, kept like the script.
+    if lock_epochs < LOCK_PERIOD_EPOCHS {
+        return Err(DaoError::InvalidWithdrawBlock);
+    }
+    Ok(lock_epochs)
+}
+
+/// The accumulate rate stored in bytes 8..16 of the `dao` field of a header.
+pub(crate) fn accumulate_rate(header: &HeaderView) -> u64 {
+    let dao = header.dao().raw_data();
+    u64::from_le_bytes(dao[8..16].try_into().expect("dao field"))
+}
+
+/// Adds the compensation of the capacity not occupied to the occupied capacity.
+pub(crate) fn maximum_withdraw(
+    capacity: u64,
+    occupied_capacity: u64,
+    deposit_accumulate_rate: u64,
+    withdraw_accumulate_rate: u64,
+) -> Result<u64, DaoError> {
+    let counted_capacity = capacity
+        .checked_sub(occupied_capacity)
+        .ok_or(DaoError::Overflow)?;
+    // The script computes in `__int128` and truncates the quotient to 64 bits. A deposit
+    // header never has a zero accumulate rate on chain, it is reported as an overflow.
+    let withdraw_counted_capacity = i128::from(counted_capacity)
+        .wrapping_mul(i128::from(withdraw_accumulate_rate))
+        .checked_div(i128::from(deposit_accumulate_rate))
+        .ok_or(DaoError::Overflow)?;
+    occupied_capacity
+        .checked_add(withdraw_counted_capacity as u64)
+        .ok_or(DaoError::Overflow)
+}
+
+/// The index of the header in `header_deps`, appended unless already there.
+fn add_header_dep(header_deps: &mut Vec<Byte32>, header: &HeaderView) -> usize {
+    let hash = header.hash();
+    match header_deps
+        .iter()
+        .position(|header_dep| *header_dep == hash)
+    {
+        Some(index) => index,
+        None => {
+            header_deps.push(hash);
+            header_deps.len() - 1
+        }
+    }
+}
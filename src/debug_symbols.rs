@@ -0,0 +1,53 @@
+//! Debug symbols of the bundled binaries, only available with the `debug-symbols` feature.
+//!
+//! The binaries in `BUNDLED_CELL` are stripped, `make` keeps their symbols in
+//! `build/<name>.debug`. Those files are embedded into `DEBUG_SYMBOLS` and looked up by the
+//! data hash of the stripped binary. To map the pcs of a failing script back to source lines,
+//! write both files with [`write_debug_files`], run the stripped binary with
+//! `ckb-debugger --mode gdb` and load the symbols in gdb via `symbol-file <name>.debug`.
+
+use crate::{get_binary, SystemScript};
+use std::{
+    borrow::Cow,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+include!(concat!(env!("OUT_DIR"), "/debug_symbols.rs"));
+include!(concat!(env!("OUT_DIR"), "/debug_symbol_index.rs"));
+
+/// Returns the debug symbols of the stripped binary whose data hash is `data_hash`.
+pub fn get_debug_symbols(data_hash: &[u8; 32]) -> Option<Cow<'static, [u8]>> {
+    DEBUG_SYMBOL_INDEX
+        .iter()
+        .find(|(hash, _)| hash == data_hash)
+        .map(|(_, path)| DEBUG_SYMBOLS.get(path).expect("bundled debug symbols"))
+}
+
+impl SystemScript {
+    /// Debug symbols of the script, `None` if they were not found when building the crate.
+    pub fn debug_symbols(self) -> Option<Cow<'static, [u8]>> {
+        get_debug_symbols(&self.data_hash())
+    }
+}
+
+/// Writes the stripped binary of `script` to `dir/<name>` and its debug symbols to
+/// `dir/<name>.debug`, returning both paths.
+pub fn write_debug_files<P: AsRef<Path>>(
+    script: SystemScript,
+    dir: P,
+) -> io::Result<(PathBuf, PathBuf)> {
+    let debug_symbols = script.debug_symbols().ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("debug symbols of {} are not bundled", script),
+        )
+    })?;
+    let dir = dir.as_ref();
+    fs::create_dir_all(dir)?;
+    let binary_path = dir.join(script.name());
+    let debug_path = dir.join(format!("{}.debug", script.name()));
+    fs::write(&binary_path, get_binary(script))?;
+    fs::write(&debug_path, debug_symbols)?;
+    Ok((binary_path, debug_path))
+}
@@ -0,0 +1,185 @@
+use crate::{get_binary, SystemScript};
+use ckb_types::{
+    core::ScriptHashType,
+    packed::{Byte, Script},
+    prelude::*,
+};
+use faster_hex::hex_string;
+use std::{
+    convert::TryFrom,
+    fmt::Write as _,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+/// Generates a dev chain spec deploying the bundled binaries.
+///
+/// The spec follows the default one of `ckb init --chain dev`, so with the same message and
+/// issued cells the genesis block matches [`DEV`](super::DEV).
+///
+/// CKB only accepts the genesis build of the DAO script, which is older than the bundled one,
+/// so the DAO cell is loaded from the binaries bundled in the node instead.
+#[derive(Clone, Debug)]
+pub struct DevChainSpec {
+    message: String,
+    issued_cells: Vec<(u64, Script)>,
+}
+
+impl Default for DevChainSpec {
+    fn default() -> Self {
+        DevChainSpec::new("ckb_dev")
+    }
+}
+
+impl DevChainSpec {
+    /// Directory of the binaries, relative to the spec file.
+    pub const CELLS_DIR: &'static str = "cells";
+
+    /// Creates a spec whose genesis cell carries `message`.
+    pub fn new(message: &str) -> Self {
+        DevChainSpec {
+            message: message.to_owned(),
+            issued_cells: Vec::new(),
+        }
+    }
+
+    /// Issues a genesis cell of `capacity` shannons locked by `lock`.
+    pub fn issue(mut self, capacity: u64, lock: Script) -> Self {
+        self.issued_cells.push((capacity, lock));
+        self
+    }
+
+    /// Renders the spec, the binaries are referenced as files in [`DevChainSpec::CELLS_DIR`].
+    pub fn to_toml(&self) -> String {
+        let zero_lock = format!(
+            "code_hash = \"0x{}\"\nargs = \"0x\"\nhash_type = \"data\"\n",
+            hex_string(&[0u8; 32])
+        );
+        let mut spec = String::new();
+        writeln!(spec, "name = \"ckb_dev\"\n").unwrap();
+        spec.push_str(DEV_GENESIS_HEADER);
+        writeln!(
+            spec,
+            "\n[genesis.genesis_cell]\nmessage = {:?}\n\n[genesis.genesis_cell.lock]\n{}",
+            self.message, zero_lock
+        )
+        .unwrap();
+
+        // Same order as the genesis output indices, the cells of the secp256k1 locks and DAO
+        // are created with Type IDs.
+        let system_cells = [
+            (SystemScript::Sighash, "100_000_0000_0000"),
+            (SystemScript::Dao, "16_000_0000_0000"),
+            (SystemScript::Secp256k1Data, "1_048_617_0000_0000"),
+            (SystemScript::Multisig, "100_000_0000_0000"),
+        ];
+        for (script, capacity) in system_cells.iter() {
+            writeln!(
+                spec,
+                "[[genesis.system_cells]]\nfile = {}\ncreate_type_id = {}\ncapacity = {}",
+                cell_file(*script),
+                script.type_hash().is_some(),
+                capacity
+            )
+            .unwrap();
+        }
+        writeln!(spec, "\n[genesis.system_cells_lock]\n{}", zero_lock).unwrap();
+
+        for script in [SystemScript::Sighash, SystemScript::Multisig].iter() {
+            writeln!(
+                spec,
+                "[[genesis.dep_groups]]\nname = \"{name}\"\nfiles = [\n  {{ file = \"{dir}/secp256k1_data\" }},\n  {{ file = \"{dir}/{name}\" }},\n]",
+                name = script.name(),
+                dir = Self::CELLS_DIR
+            )
+            .unwrap();
+        }
+        writeln!(
+            spec,
+            "\n[genesis.bootstrap_lock]\ncode_hash = \"0x{}\"\nargs = \"0x\"\nhash_type = \"type\"",
+            hex_string(&[0u8; 32])
+        )
+        .unwrap();
+
+        for (capacity, lock) in &self.issued_cells {
+            writeln!(
+                spec,
+                "\n[[genesis.issued_cells]]\ncapacity = {}\nlock.code_hash = \"0x{}\"\nlock.args = \"0x{}\"\nlock.hash_type = \"{}\"",
+                capacity,
+                hex_string(lock.code_hash().as_slice()),
+                hex_string(&lock.args().raw_data()),
+                hash_type_name(lock.hash_type())
+            )
+            .unwrap();
+        }
+        spec.push('\n');
+        spec.push_str(DEV_PARAMS);
+        spec
+    }
+
+    /// Writes the binaries into `dir/cells` and the spec to `dir/dev.toml`, returning the path
+    /// of the spec. The DAO binary is not written, the spec loads it from the node.
+    pub fn write<P: AsRef<Path>>(&self, dir: P) -> io::Result<PathBuf> {
+        let dir = dir.as_ref();
+        let cells_dir = dir.join(Self::CELLS_DIR);
+        fs::create_dir_all(&cells_dir)?;
+        for script in SystemScript::iter().filter(|script| *script != SystemScript::Dao) {
+            fs::write(cells_dir.join(script.name()), get_binary(script))?;
+        }
+        let spec_path = dir.join("dev.toml");
+        fs::write(&spec_path, self.to_toml())?;
+        Ok(spec_path)
+    }
+}
+
+const DEV_GENESIS_HEADER: &str = "[genesis]
+version = 0
+parent_hash = \"0x0000000000000000000000000000000000000000000000000000000000000000\"
+timestamp = 0
+compact_target = 0x20010000
+uncles_hash = \"0x0000000000000000000000000000000000000000000000000000000000000000\"
+nonce = \"0x0\"
+";
+
+const DEV_PARAMS: &str = "[params]
+initial_primary_epoch_reward = 1_917_808_21917808
+secondary_epoch_reward = 613_698_63013698
+max_block_cycles = 10_000_000_000
+cellbase_maturity = 0
+primary_epoch_reward_halving_interval = 8760
+epoch_duration_target = 14400
+genesis_epoch_length = 1000
+permanent_difficulty_in_dummy = true
+
+[params.hardfork]
+rfc_0028 = 0
+rfc_0029 = 0
+rfc_0030 = 0
+rfc_0031 = 0
+rfc_0032 = 0
+rfc_0036 = 0
+rfc_0038 = 0
+
+[pow]
+func = \"Dummy\"
+";
+
+/// The resource of a system cell, the DAO one refers to the binary bundled in the node.
+fn cell_file(script: SystemScript) -> String {
+    match script {
+        SystemScript::Dao => format!("{{ bundled = \"specs/cells/{}\" }}", script.name()),
+        _ => format!(
+            "{{ file = \"{}/{}\" }}",
+            DevChainSpec::CELLS_DIR,
+            script.name()
+        ),
+    }
+}
+
+fn hash_type_name(hash_type: Byte) -> &'static str {
+    match ScriptHashType::try_from(hash_type) {
+        Ok(ScriptHashType::Type) => "type",
+        Ok(ScriptHashType::Data1) => "data1",
+        _ => "data",
+    }
+}
@@ -0,0 +1,147 @@
+//! Deployments of the system scripts in the genesis blocks of the CKB networks.
+//!
+//! The script cells are outputs of the genesis cellbase transaction, at
+//! [`SystemScript::genesis_output_index`]. The secp256k1 locks must be loaded together with
+//! `secp256k1_data`, so the second genesis transaction creates a dep group cell for each of
+//! them, sighash at index 0 and multisig at index 1.
+//!
+//! These genesis blocks deploy the genesis build of the DAO script, which is older than the
+//! bundled one, see [`crate::versions`]. It is referenced by its Type ID like the locks.
+
+use crate::SystemScript;
+use ckb_types::{
+    bytes::Bytes,
+    core::{DepType, ScriptHashType},
+    packed::{Byte32, CellDep, OutPoint, Script},
+    prelude::*,
+};
+
+#[cfg(all(
+    feature = "sighash",
+    feature = "multisig",
+    feature = "dao",
+    feature = "secp256k1-data"
+))]
+mod dev;
+
+#[cfg(all(
+    feature = "sighash",
+    feature = "multisig",
+    feature = "dao",
+    feature = "secp256k1-data"
+))]
+pub use dev::DevChainSpec;
+
+/// Where the system scripts are deployed on a network.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Deployment {
+    /// Hash of the genesis cellbase transaction, which creates the script cells.
+    pub genesis_tx_hash: [u8; 32],
+    /// Hash of the genesis transaction creating the dep group cells.
+    pub dep_group_tx_hash: [u8; 32],
+}
+
+/// Deployment on the mainnet, Lina.
+pub const MAINNET: Deployment = Deployment {
+    genesis_tx_hash: [
+        0xe2, 0xfb, 0x19, 0x98, 0x10, 0xd4, 0x9a, 0x4d, 0x8b, 0xee, 0xc5, 0x67, 0x18, 0xba, 0x25,
+        0x93, 0xb6, 0x65, 0xdb, 0x9d, 0x52, 0x29, 0x9a, 0x0f, 0x9e, 0x6e, 0x75, 0x41, 0x6d, 0x73,
+        0xff, 0x5c,
+    ],
+    dep_group_tx_hash: [
+        0x71, 0xa7, 0xba, 0x8f, 0xc9, 0x63, 0x49, 0xfe, 0xa0, 0xed, 0x3a, 0x5c, 0x47, 0x99, 0x2e,
+        0x3b, 0x40, 0x84, 0xb0, 0x31, 0xa4, 0x22, 0x64, 0xa0, 0x18, 0xe0, 0x07, 0x2e, 0x81, 0x72,
+        0xe4, 0x6c,
+    ],
+};
+
+/// Deployment on the testnet, Pudge.
+pub const TESTNET: Deployment = Deployment {
+    genesis_tx_hash: [
+        0x8f, 0x8c, 0x79, 0xeb, 0x66, 0x71, 0x70, 0x96, 0x33, 0xfe, 0x6a, 0x46, 0xde, 0x93, 0xc0,
+        0xfe, 0xdc, 0x9c, 0x1b, 0x8a, 0x65, 0x27, 0xa1, 0x8d, 0x39, 0x83, 0x87, 0x95, 0x42, 0x63,
+        0x5c, 0x9f,
+    ],
+    dep_group_tx_hash: [
+        0xf8, 0xde, 0x3b, 0xb4, 0x7d, 0x05, 0x5c, 0xdf, 0x46, 0x0d, 0x93, 0xa2, 0xa6, 0xe1, 0xb0,
+        0x5f, 0x74, 0x32, 0xf9, 0x77, 0x7c, 0x8c, 0x47, 0x4a, 0xbf, 0x4e, 0xec, 0x1d, 0x4a, 0xee,
+        0x5d, 0x37,
+    ],
+};
+
+/// Deployment on a dev chain initialized with the default spec of `ckb init --chain dev`.
+pub const DEV: Deployment = Deployment {
+    genesis_tx_hash: [
+        0xa5, 0x63, 0x88, 0x4b, 0x36, 0x86, 0x07, 0x8e, 0xc7, 0xe7, 0x67, 0x7a, 0x5f, 0x86, 0x44,
+        0x9b, 0x15, 0xcf, 0x26, 0x93, 0xf3, 0xc1, 0x24, 0x17, 0x66, 0xc6, 0x99, 0x6f, 0x20, 0x6c,
+        0xc5, 0x41,
+    ],
+    dep_group_tx_hash: [
+        0xac, 0xe5, 0xea, 0x83, 0xc4, 0x78, 0xbb, 0x86, 0x6e, 0xdf, 0x12, 0x2f, 0xf8, 0x62, 0x08,
+        0x57, 0x89, 0x15, 0x8f, 0x5c, 0xbf, 0xf1, 0x55, 0xb7, 0xbb, 0x5f, 0x13, 0x05, 0x85, 0x55,
+        0xb7, 0x08,
+    ],
+};
+
+impl Deployment {
+    /// Out point of the cell holding the script binary.
+    pub fn code_out_point(&self, script: SystemScript) -> OutPoint {
+        OutPoint::new(
+            self.genesis_tx_hash.pack(),
+            script.genesis_output_index() as u32,
+        )
+    }
+
+    /// Out point of the dep group cell bundling the script with `secp256k1_data`, `None` for
+    /// the scripts deployed without one.
+    pub fn dep_group_out_point(&self, script: SystemScript) -> Option<OutPoint> {
+        match script {
+            #[cfg(feature = "sighash")]
+            SystemScript::Sighash => Some(OutPoint::new(self.dep_group_tx_hash.pack(), 0)),
+            #[cfg(feature = "multisig")]
+            SystemScript::Multisig => Some(OutPoint::new(self.dep_group_tx_hash.pack(), 1)),
+            #[cfg(feature = "dao")]
+            SystemScript::Dao => None,
+            #[cfg(feature = "secp256k1-data")]
+            SystemScript::Secp256k1Data => None,
+        }
+    }
+
+    /// Cell dep to load the script in a transaction, the dep group if there is one.
+    pub fn cell_dep(&self, script: SystemScript) -> CellDep {
+        let (out_point, dep_type) = match self.dep_group_out_point(script) {
+            Some(out_point) => (out_point, DepType::DepGroup),
+            None => (self.code_out_point(script), DepType::Code),
+        };
+        CellDep::new_builder()
+            .out_point(out_point)
+            .dep_type(dep_type.into())
+            .build()
+    }
+
+    /// `hash_type` to reference the script with, `type` for the scripts deployed with a Type ID.
+    pub fn hash_type(&self, script: SystemScript) -> ScriptHashType {
+        if script.type_hash().is_some() {
+            ScriptHashType::Type
+        } else {
+            ScriptHashType::Data
+        }
+    }
+
+    /// `code_hash` to reference the script with, matching [`Deployment::hash_type`].
+    pub fn code_hash(&self, script: SystemScript) -> Byte32 {
+        script
+            .code_hash(self.hash_type(script))
+            .expect("code hash of deployed script")
+            .pack()
+    }
+
+    /// Builds a script running `script` with `args`.
+    pub fn script(&self, script: SystemScript, args: Bytes) -> Script {
+        Script::new_builder()
+            .code_hash(self.code_hash(script))
+            .hash_type(self.hash_type(script).into())
+            .args(args.pack())
+            .build()
+    }
+}
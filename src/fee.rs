@@ -0,0 +1,159 @@
+//! Estimates the size and fee of a transaction before it is signed.
+//!
+//! The signatures don't change the size of a transaction, so filling the witnesses with
+//! placeholders of the final size gives the size of the signed transaction. The fee rate is in
+//! shannons per 1000 bytes of the transaction serialized in a block.
+
+use crate::{
+    multisig::MultisigConfig,
+    sighash::InputWitnesses,
+    signer::{group_inputs, CellOutputProvider, SignError},
+};
+#[cfg(feature = "sighash")]
+use crate::{sighash::SIGNATURE_SIZE, signer::is_sighash_lock};
+use ckb_types::{
+    bytes::Bytes,
+    core::TransactionView,
+    packed::{Byte32, Script},
+    prelude::*,
+};
+use std::{
+    collections::{BTreeMap, HashMap},
+    convert::TryFrom,
+};
+
+/// The `lock` field of the first witness of a script group.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum LockWitness {
+    /// A `secp256k1_blake160_sighash_all` signature.
+    #[cfg(feature = "sighash")]
+    Sighash,
+    /// A `secp256k1_blake160_multisig_all` script followed by `threshold` signatures.
+    Multisig(MultisigConfig),
+    /// The lock of another script, this many bytes long.
+    Other(usize),
+}
+
+impl LockWitness {
+    /// The placeholder of the `lock` field, zeroed except for the multisig script.
+    pub fn placeholder(&self) -> Bytes {
+        match self {
+            #[cfg(feature = "sighash")]
+            LockWitness::Sighash => Bytes::from(vec![0; SIGNATURE_SIZE]),
+            LockWitness::Multisig(config) => config.placeholder(),
+            LockWitness::Other(size) => Bytes::from(vec![0; *size]),
+        }
+    }
+}
+
+/// Size and fee of a transaction.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Estimate {
+    /// The transaction with placeholder witnesses.
+    pub tx: TransactionView,
+    /// Size of the transaction serialized in a block.
+    pub size: usize,
+}
+
+impl Estimate {
+    /// Fee in shannons at `fee_rate` shannons per 1000 bytes, `None` if it overflows 64 bits.
+    pub fn fee(&self, fee_rate: u64) -> Option<u64> {
+        calculate_fee(self.size, fee_rate)
+    }
+}
+
+/// Fee in shannons of a transaction of `size` bytes at `fee_rate` shannons per 1000 bytes,
+/// rounded up, `None` if it overflows 64 bits.
+pub fn calculate_fee(size: usize, fee_rate: u64) -> Option<u64> {
+    let fee = size as u128 * u128::from(fee_rate);
+    let mut shannons = fee / 1000;
+    if shannons * 1000 < fee {
+        shannons += 1;
+    }
+    u64::try_from(shannons).ok()
+}
+
+/// Fills the witnesses of an unsigned transaction with placeholders of their signed size.
+///
+/// Groups locked by `secp256k1_blake160_sighash_all` are recognized, the other locks must be
+/// described with [`WitnessEstimator::lock`]. The witnesses of the groups with unknown locks
+/// are left untouched.
+#[derive(Clone, Debug, Default)]
+pub struct WitnessEstimator {
+    locks: HashMap<Byte32, LockWitness>,
+    dao_header_indices: BTreeMap<usize, u64>,
+}
+
+impl WitnessEstimator {
+    /// Creates an estimator without lock descriptions.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Describes the witness of the group locked by `lock`.
+    pub fn lock(mut self, lock: &Script, witness: LockWitness) -> Self {
+        self.locks.insert(lock.calc_script_hash(), witness);
+        self
+    }
+
+    /// Marks the input at `input_index` as a withdrawing DAO cell, whose witness keeps the
+    /// index of the deposit block in the header deps as the `input_type`.
+    pub fn dao_withdraw(mut self, input_index: usize, header_dep_index: u64) -> Self {
+        self.dao_header_indices
+            .insert(input_index, header_dep_index);
+        self
+    }
+
+    fn lock_witness(&self, lock: &Script) -> Option<LockWitness> {
+        if let Some(witness) = self.locks.get(&lock.calc_script_hash()) {
+            return Some(witness.clone());
+        }
+        #[cfg(feature = "sighash")]
+        {
+            if is_sighash_lock(lock) {
+                return Some(LockWitness::Sighash);
+            }
+        }
+        None
+    }
+
+    /// Returns `tx` with the placeholder witnesses.
+    ///
+    /// Missing witnesses of the inputs are filled with empty ones first, the placeholders
+    /// replace the `lock` and DAO `input_type` of the existing `WitnessArgs`.
+    pub fn fill<P: CellOutputProvider>(
+        &self,
+        tx: &TransactionView,
+        provider: &P,
+    ) -> Result<TransactionView, SignError> {
+        let mut witnesses = InputWitnesses::new(tx, tx.inputs().len());
+        for (&index, header_dep_index) in &self.dao_header_indices {
+            if index >= tx.inputs().len() {
+                return Err(SignError::InputCellNotFound(index));
+            }
+            let input_type = Bytes::from(header_dep_index.to_le_bytes().to_vec());
+            witnesses.update_args(index, |builder| builder.input_type(Some(input_type).pack()))?;
+        }
+        for group in group_inputs(tx, provider)? {
+            let lock = match self.lock_witness(&group.lock) {
+                Some(witness) => witness.placeholder(),
+                None => continue,
+            };
+            witnesses.update_args(group.input_indices[0], |builder| {
+                builder.lock(Some(lock).pack())
+            })?;
+        }
+        Ok(witnesses.set_to(tx))
+    }
+
+    /// Fills the placeholder witnesses and measures the transaction.
+    pub fn estimate<P: CellOutputProvider>(
+        &self,
+        tx: &TransactionView,
+        provider: &P,
+    ) -> Result<Estimate, SignError> {
+        let tx = self.fill(tx, provider)?;
+        let size = tx.data().serialized_size_in_block();
+        Ok(Estimate { tx, size })
+    }
+}
@@ -1,12 +1,85 @@
 //! pub use const BUNDLED_CELL: Files
+//! pub use const BUNDLED_HISTORY: Files
 //! pub use const CODE_HASH_DAO: [u8; 32]
 //! pub use const CODE_HASH_SECP256K1_BLAKE160_SIGHASH_ALL: [u8; 32]
-//! pub use const CODE_HASH_SECP256K1_RIPEMD160_SHA256_SIGHASH_ALL: [u8; 32]
+//! pub use const CODE_HASH_SECP256K1_BLAKE160_MULTISIG_ALL: [u8; 32]
+//! pub use const CODE_HASH_SECP256K1_DATA: [u8; 32]
+//!
+//! pub use const TYPE_HASH_DAO: [u8; 32]
+//! pub use const TYPE_HASH_SECP256K1_BLAKE160_SIGHASH_ALL: [u8; 32]
+//! pub use const TYPE_HASH_SECP256K1_BLAKE160_MULTISIG_ALL: [u8; 32]
+//! pub use const ELF_METADATA_DAO: ElfMetadata
+//! pub use const ELF_METADATA_SECP256K1_BLAKE160_SIGHASH_ALL: ElfMetadata
+//! pub use const ELF_METADATA_SECP256K1_BLAKE160_MULTISIG_ALL: ElfMetadata
+//! pub use enum SystemScript
+//! pub use fn get_binary(SystemScript) -> Bytes
+//! pub use struct ScriptVersion
+//! pub use fn sighash_all_message(&TransactionView, &[usize]) -> Result<[u8; 32], MessageError>
+//! pub use fn blake160(&[u8]) -> [u8; 20]
+//!
+//! The bundled binaries are listed in `specs/manifest.toml`. Each of them is guarded by a
+//! cargo feature, `sighash`, `multisig`, `dao` and `secp256k1-data`, all enabled by default.
+//! The `CODE_HASH_*` constant and `SystemScript` variant of a script only exist when its
+//! feature is enabled.
+//!
+//! The out points and cell deps of the scripts deployed on mainnet, testnet and dev chains are
+//! described in the `deployment` module.
+//!
+//! Historical binaries of the scripts are indexed in the `versions` module, they are only
+//! embedded into `BUNDLED_HISTORY` with the `history` feature.
+//!
+//! The multisig script of a `secp256k1_blake160_multisig_all` lock is parsed and validated by
+//! `multisig::MultisigConfig`, with the same checks as the lock. Co-signers collect their
+//! signatures separately in a `multisig::PartialTx`. The lock args, optionally carrying a
+//! `since::Since` constraint, are `multisig::MultisigLockArgs`. `Since` compares values like
+//! the locks do, so time locks can be checked off chain.
+//!
+//! The `dao` module builds the deposit and withdraw transactions of the NervosDAO, and
+//! calculates the capacity withdrawn and the epoch from which it can be withdrawn.
+//!
+//! The `address` module converts lock scripts to mainnet and testnet addresses and back.
+//!
+//! The `fee` module fills unsigned transactions with placeholder witnesses of their signed
+//! size to estimate the fee.
+//!
+//! The exit codes of the scripts are typed in the `script_error` module, which also decodes
+//! them from a failed script verification. The `verifier` module checks transactions with
+//! native implementations of the scripts, failing with the same exit codes.
+//!
+//! The `debug-symbols` feature additionally embeds the debug symbols split from the binaries,
+//! see the `debug_symbols` module.
 
 #![allow(clippy::unreadable_literal)]
 
 include!(concat!(env!("OUT_DIR"), "/bundled.rs"));
 include!(concat!(env!("OUT_DIR"), "/code_hashes.rs"));
+include!(concat!(env!("OUT_DIR"), "/elf_metadata.rs"));
+include!(concat!(env!("OUT_DIR"), "/history.rs"));
+include!(concat!(env!("OUT_DIR"), "/script_versions.rs"));
+
+pub mod address;
+pub mod dao;
+#[cfg(feature = "debug-symbols")]
+pub mod debug_symbols;
+pub mod deployment;
+pub mod fee;
+pub mod multisig;
+pub mod script_error;
+pub mod sighash;
+pub mod signer;
+pub mod since;
+mod system_script;
+pub mod type_id;
+pub mod verifier;
+pub mod versions;
+
+pub use sighash::{blake160, sighash_all_message};
+pub use system_script::{get_binary, ElfMetadata, SystemScript};
+pub use type_id::{
+    TYPE_HASH_DAO, TYPE_HASH_SECP256K1_BLAKE160_MULTISIG_ALL,
+    TYPE_HASH_SECP256K1_BLAKE160_SIGHASH_ALL,
+};
+pub use versions::ScriptVersion;
 
 #[cfg(test)]
 mod tests;
@@ -0,0 +1,74 @@
+use super::BLAKE160_SIZE;
+use crate::since::Since;
+use ckb_types::{bytes::Bytes, core::TransactionView, packed::CellInput, prelude::*};
+
+/// Size of the `since` following the multisig script hash in the lock args.
+pub const SINCE_SIZE: usize = 8;
+
+/// Args of a `secp256k1_blake160_multisig_all` lock.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct MultisigLockArgs {
+    /// Blake160 hash of the multisig script.
+    pub hash160: [u8; BLAKE160_SIZE],
+    /// The `since` every input of the group must satisfy.
+    pub since: Option<Since>,
+}
+
+impl MultisigLockArgs {
+    /// Serializes the args, the hash followed by the little endian `since` if any.
+    pub fn to_bytes(&self) -> Bytes {
+        let mut bytes = self.hash160.to_vec();
+        if let Some(since) = self.since {
+            bytes.extend_from_slice(&since.as_u64().to_le_bytes());
+        }
+        bytes.into()
+    }
+
+    /// Parses the args, `None` if they are neither 20 nor 28 bytes long.
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() != BLAKE160_SIZE && bytes.len() != BLAKE160_SIZE + SINCE_SIZE {
+            return None;
+        }
+        let mut hash160 = [0u8; BLAKE160_SIZE];
+        hash160.copy_from_slice(&bytes[..BLAKE160_SIZE]);
+        let since = if bytes.len() > BLAKE160_SIZE {
+            let mut since = [0u8; SINCE_SIZE];
+            since.copy_from_slice(&bytes[BLAKE160_SIZE..]);
+            Some(Since::from_raw_value(u64::from_le_bytes(since)))
+        } else {
+            None
+        };
+        Some(MultisigLockArgs { hash160, since })
+    }
+
+    /// The constraint checked on the inputs, args without `since` require zero flags.
+    pub fn since_constraint(&self) -> Since {
+        self.since.unwrap_or_default()
+    }
+
+    /// Sets the `since` of the group inputs at `input_indices` which don't satisfy the
+    /// constraint to the constraint itself, so the lock accepts all of them.
+    ///
+    /// The `since` is part of the transaction hash, call it before signing.
+    pub fn set_group_since(
+        &self,
+        tx: &TransactionView,
+        input_indices: &[usize],
+    ) -> TransactionView {
+        let constraint = self.since_constraint();
+        let inputs: Vec<CellInput> = tx
+            .inputs()
+            .into_iter()
+            .enumerate()
+            .map(|(index, input)| {
+                let since = Since::from_raw_value(input.since().unpack());
+                if input_indices.contains(&index) && !since.satisfies(constraint) {
+                    input.as_builder().since(constraint.as_u64().pack()).build()
+                } else {
+                    input
+                }
+            })
+            .collect();
+        tx.as_advanced_builder().set_inputs(inputs).build()
+    }
+}
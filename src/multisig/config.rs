@@ -0,0 +1,177 @@
+pub use crate::sighash::BLAKE160_SIZE;
+use crate::{
+    script_error::MultisigError,
+    sighash::{blake160, SIGNATURE_SIZE},
+};
+use ckb_types::bytes::Bytes;
+use std::{error, fmt};
+
+/// Size of the `S | R | M | N` flags in front of the pubkey hashes.
+pub const FLAGS_SIZE: usize = 4;
+
+/// Invalid multisig scripts, each rejected by the lock with the same category of error.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ConfigError {
+    /// The reserved `S` byte is not zero.
+    InvalidReserveField,
+    /// There are no pubkeys, or more than 255.
+    InvalidPubkeysCnt,
+    /// The threshold is zero or larger than the number of pubkeys.
+    InvalidThreshold,
+    /// `require_first_n` is larger than the threshold.
+    InvalidRequireFirstN,
+    /// The script is not `4 + 20 * N` bytes long.
+    InvalidLength,
+}
+
+impl ConfigError {
+    /// The error of the lock for this error.
+    pub fn lock_error(self) -> MultisigError {
+        match self {
+            ConfigError::InvalidReserveField => MultisigError::InvalidReserveField,
+            ConfigError::InvalidPubkeysCnt => MultisigError::InvalidPubkeysCnt,
+            ConfigError::InvalidThreshold => MultisigError::InvalidThreshold,
+            ConfigError::InvalidRequireFirstN => MultisigError::InvalidRequireFirstN,
+            // The lock checks the size of the whole lock field instead.
+            ConfigError::InvalidLength => MultisigError::WitnessSize,
+        }
+    }
+
+    /// Exit code of the lock for this error.
+    pub fn exit_code(self) -> i8 {
+        self.lock_error().exit_code()
+    }
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let reason = match self {
+            ConfigError::InvalidReserveField => "reserved field is not zero",
+            ConfigError::InvalidPubkeysCnt => "pubkeys count must be between 1 and 255",
+            ConfigError::InvalidThreshold => "threshold must be between 1 and pubkeys count",
+            ConfigError::InvalidRequireFirstN => "require_first_n is larger than threshold",
+            ConfigError::InvalidLength => "multisig script length mismatches pubkeys count",
+        };
+        write!(f, "invalid multisig script: {}", reason)
+    }
+}
+
+impl error::Error for ConfigError {}
+
+/// The multisig script of a `secp256k1_blake160_multisig_all` lock.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct MultisigConfig {
+    /// Number of leading pubkeys which must sign.
+    pub require_first_n: u8,
+    /// Number of signatures required.
+    pub threshold: u8,
+    /// Blake160 hashes of the pubkeys.
+    pub pubkey_hashes: Vec<[u8; BLAKE160_SIZE]>,
+}
+
+impl MultisigConfig {
+    /// Creates a config, failing if the lock would reject it.
+    pub fn new(
+        require_first_n: u8,
+        threshold: u8,
+        pubkey_hashes: Vec<[u8; BLAKE160_SIZE]>,
+    ) -> Result<Self, ConfigError> {
+        let config = MultisigConfig {
+            require_first_n,
+            threshold,
+            pubkey_hashes,
+        };
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Checks the config in the same order as the lock.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        if self.pubkey_hashes.is_empty() || self.pubkey_hashes.len() > usize::from(u8::MAX) {
+            return Err(ConfigError::InvalidPubkeysCnt);
+        }
+        if self.threshold == 0 || usize::from(self.threshold) > self.pubkey_hashes.len() {
+            return Err(ConfigError::InvalidThreshold);
+        }
+        if self.require_first_n > self.threshold {
+            return Err(ConfigError::InvalidRequireFirstN);
+        }
+        Ok(())
+    }
+
+    /// Serializes the multisig script, `S | R | M | N | PubKeyHash...`.
+    ///
+    /// The pubkeys count is truncated to a byte, call [`MultisigConfig::validate`] first.
+    pub fn to_bytes(&self) -> Bytes {
+        let mut bytes = Vec::with_capacity(self.script_size());
+        bytes.extend_from_slice(&[
+            0,
+            self.require_first_n,
+            self.threshold,
+            self.pubkey_hashes.len() as u8,
+        ]);
+        for pubkey_hash in &self.pubkey_hashes {
+            bytes.extend_from_slice(pubkey_hash);
+        }
+        bytes.into()
+    }
+
+    /// Parses a multisig script, running the same checks as the lock.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, ConfigError> {
+        if bytes.len() < FLAGS_SIZE {
+            return Err(ConfigError::InvalidLength);
+        }
+        if bytes[0] != 0 {
+            return Err(ConfigError::InvalidReserveField);
+        }
+        let (require_first_n, threshold, pubkeys_cnt) = (bytes[1], bytes[2], bytes[3]);
+        if pubkeys_cnt == 0 {
+            return Err(ConfigError::InvalidPubkeysCnt);
+        }
+        if threshold == 0 || threshold > pubkeys_cnt {
+            return Err(ConfigError::InvalidThreshold);
+        }
+        if require_first_n > threshold {
+            return Err(ConfigError::InvalidRequireFirstN);
+        }
+        if bytes.len() != FLAGS_SIZE + BLAKE160_SIZE * usize::from(pubkeys_cnt) {
+            return Err(ConfigError::InvalidLength);
+        }
+        let pubkey_hashes = bytes[FLAGS_SIZE..]
+            .chunks_exact(BLAKE160_SIZE)
+            .map(|chunk| {
+                let mut pubkey_hash = [0u8; BLAKE160_SIZE];
+                pubkey_hash.copy_from_slice(chunk);
+                pubkey_hash
+            })
+            .collect();
+        Ok(MultisigConfig {
+            require_first_n,
+            threshold,
+            pubkey_hashes,
+        })
+    }
+
+    /// Blake160 hash of the multisig script, which is the lock args.
+    pub fn hash160(&self) -> [u8; BLAKE160_SIZE] {
+        blake160(&self.to_bytes())
+    }
+
+    /// Size of the multisig script.
+    pub fn script_size(&self) -> usize {
+        FLAGS_SIZE + BLAKE160_SIZE * self.pubkey_hashes.len()
+    }
+
+    /// Size of the lock field in the witness, the multisig script followed by `threshold`
+    /// signatures.
+    pub fn lock_size(&self) -> usize {
+        self.script_size() + SIGNATURE_SIZE * usize::from(self.threshold)
+    }
+
+    /// The lock field while signing, the multisig script followed by zeroed signatures.
+    pub fn placeholder(&self) -> Bytes {
+        let mut lock = self.to_bytes().to_vec();
+        lock.resize(self.lock_size(), 0);
+        lock.into()
+    }
+}
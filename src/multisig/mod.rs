@@ -0,0 +1,22 @@
+//! Helpers for the `secp256k1_blake160_multisig_all` lock.
+//!
+//! The lock field of the first witness in a multisig group is the multisig script followed by
+//! the signatures:
+//!
+//! ```text
+//! S | R | M | N | PubKeyHash1 | ... | PubKeyHashN | Signature1 | ... | SignatureM
+//! ```
+//!
+//! `S` is reserved and must be zero, `R` is the number of leading pubkeys which must sign, `M`
+//! is the threshold and `N` is the number of pubkeys. The script args are the blake160 hash of
+//! the multisig script, optionally followed by a `since` value.
+
+mod args;
+mod config;
+#[cfg(feature = "multisig")]
+mod partial;
+
+pub use args::{MultisigLockArgs, SINCE_SIZE};
+pub use config::{ConfigError, MultisigConfig, BLAKE160_SIZE, FLAGS_SIZE};
+#[cfg(feature = "multisig")]
+pub use partial::{MultisigGroup, PartialTx, PartialTxError};
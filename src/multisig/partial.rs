@@ -0,0 +1,398 @@
+use super::{MultisigConfig, BLAKE160_SIZE};
+use crate::{
+    sighash::{blake160, signing_message, InputWitnesses, MessageError, SIGNATURE_SIZE},
+    signer::{group_inputs, runs_script, CellOutputProvider, SignError},
+    SystemScript,
+};
+use ckb_crypto::secp::{Privkey, Signature};
+use ckb_types::{
+    bytes::Bytes,
+    core::TransactionView,
+    packed::{self, BytesVec, Transaction, Uint32, Uint32Vec, WitnessArgs},
+    prelude::*,
+    H256,
+};
+use std::{collections::BTreeMap, error, fmt};
+
+/// Errors when collecting the signatures of a multisig transaction.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PartialTxError {
+    /// Grouping the inputs or generating the signing message fails.
+    Sign(SignError),
+    /// The private key is invalid, no pubkey is derived from it.
+    InvalidKey,
+    /// There is no multisig group at this position.
+    GroupNotFound(usize),
+    /// The signature cannot be parsed, or no pubkey is recovered from it.
+    InvalidSignature,
+    /// The pubkey of the signature is not listed in the config of the group.
+    UnknownSigner([u8; BLAKE160_SIZE]),
+    /// The partially signed transactions being merged sign different transactions.
+    TxMismatch,
+    /// The group whose first input is at this index has less than `threshold` signatures.
+    NotEnoughSignatures(usize),
+    /// The group whose first input is at this index misses the signature of one of its first
+    /// `require_first_n` pubkeys.
+    MissingRequiredSignature(usize, [u8; BLAKE160_SIZE]),
+    /// The serialized partially signed transaction is malformed.
+    InvalidFormat,
+}
+
+impl fmt::Display for PartialTxError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PartialTxError::Sign(err) => write!(f, "{}", err),
+            PartialTxError::InvalidKey => write!(f, "invalid private key"),
+            PartialTxError::GroupNotFound(position) => {
+                write!(f, "multisig group {} is not found", position)
+            }
+            PartialTxError::InvalidSignature => write!(f, "invalid signature"),
+            PartialTxError::UnknownSigner(pubkey_hash) => {
+                write!(f, "signer 0x")?;
+                for byte in pubkey_hash {
+                    write!(f, "{:02x}", byte)?;
+                }
+                write!(f, " is not in the multisig config")
+            }
+            PartialTxError::TxMismatch => write!(f, "partially signed transactions mismatch"),
+            PartialTxError::NotEnoughSignatures(index) => {
+                write!(f, "not enough signatures for the group of input {}", index)
+            }
+            PartialTxError::MissingRequiredSignature(index, _) => write!(
+                f,
+                "missing required signature for the group of input {}",
+                index
+            ),
+            PartialTxError::InvalidFormat => {
+                write!(f, "malformed partially signed transaction")
+            }
+        }
+    }
+}
+
+impl error::Error for PartialTxError {}
+
+impl From<SignError> for PartialTxError {
+    fn from(err: SignError) -> Self {
+        PartialTxError::Sign(err)
+    }
+}
+
+impl From<MessageError> for PartialTxError {
+    fn from(err: MessageError) -> Self {
+        PartialTxError::Sign(SignError::Message(err))
+    }
+}
+
+/// A multisig script group and the signatures collected for it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MultisigGroup {
+    /// Indices of the inputs in ascending order.
+    pub input_indices: Vec<usize>,
+    /// The multisig script whose hash is the lock args.
+    pub config: MultisigConfig,
+    /// Verified signatures keyed by the blake160 hash of the signing pubkey.
+    pub signatures: BTreeMap<[u8; BLAKE160_SIZE], [u8; SIGNATURE_SIZE]>,
+}
+
+/// A transaction whose `secp256k1_blake160_multisig_all` groups are signed by the co-signers
+/// separately.
+///
+/// The first witness of each group already holds the multisig script and zeroed signatures, so
+/// every co-signer signs the same message. The partials signed by different co-signers are
+/// combined with [`PartialTx::merge`], and [`PartialTx::finalize`] fills in the signatures once
+/// enough are collected.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PartialTx {
+    tx: TransactionView,
+    groups: Vec<MultisigGroup>,
+}
+
+impl PartialTx {
+    /// Prepares `tx` for signing the multisig groups whose lock args start with the hash of one
+    /// of `configs`. The other groups are left untouched.
+    pub fn new<P: CellOutputProvider>(
+        tx: &TransactionView,
+        provider: &P,
+        configs: &[MultisigConfig],
+    ) -> Result<Self, PartialTxError> {
+        let mut witnesses = InputWitnesses::new(tx, tx.inputs().len());
+
+        let mut groups = Vec::new();
+        for group in group_inputs(tx, provider)? {
+            if !runs_script(&group.lock, SystemScript::Multisig) {
+                continue;
+            }
+            let args = group.lock.args().raw_data();
+            let config = match configs.iter().find(|config| {
+                args.len() >= BLAKE160_SIZE && args[..BLAKE160_SIZE] == config.hash160()
+            }) {
+                Some(config) => config.clone(),
+                None => continue,
+            };
+            let group = MultisigGroup {
+                input_indices: group.input_indices,
+                config,
+                signatures: BTreeMap::new(),
+            };
+            let first = group.input_indices[0];
+            witnesses.update_args(first, |builder| {
+                builder.lock(Some(group.config.placeholder()).pack())
+            })?;
+            groups.push(group);
+        }
+
+        let tx = witnesses.set_to(tx);
+        Ok(PartialTx { tx, groups })
+    }
+
+    /// The transaction being signed.
+    pub fn tx(&self) -> &TransactionView {
+        &self.tx
+    }
+
+    /// The multisig groups, ordered by their first inputs.
+    pub fn groups(&self) -> &[MultisigGroup] {
+        &self.groups
+    }
+
+    /// The message signed by the co-signers of the group at `position`.
+    pub fn message(&self, position: usize) -> Result<[u8; 32], PartialTxError> {
+        let group = self
+            .groups
+            .get(position)
+            .ok_or(PartialTxError::GroupNotFound(position))?;
+        Ok(signing_message(
+            &self.tx,
+            &group.input_indices,
+            group.config.placeholder(),
+        )?)
+    }
+
+    /// Signs every group listing the pubkey of `key`, returning the number of signed groups.
+    pub fn sign(&mut self, key: &Privkey) -> Result<usize, PartialTxError> {
+        let pubkey = key.pubkey().map_err(|_| PartialTxError::InvalidKey)?;
+        let pubkey_hash = blake160(&pubkey.serialize());
+        let mut signed = 0;
+        for position in 0..self.groups.len() {
+            if !self.groups[position]
+                .config
+                .pubkey_hashes
+                .contains(&pubkey_hash)
+            {
+                continue;
+            }
+            let first = self.groups[position].input_indices[0];
+            let message = self.message(position)?;
+            let signature = key
+                .sign_recoverable(&H256::from(message))
+                .map_err(|_| SignError::SigningFailed(first))?;
+            self.groups[position]
+                .signatures
+                .insert(pubkey_hash, to_signature(&signature.serialize())?);
+            signed += 1;
+        }
+        Ok(signed)
+    }
+
+    /// Adds a signature produced elsewhere for the group at `position`, returning the hash of
+    /// the recovered pubkey.
+    pub fn add_signature(
+        &mut self,
+        position: usize,
+        signature: &[u8],
+    ) -> Result<[u8; BLAKE160_SIZE], PartialTxError> {
+        let message = self.message(position)?;
+        let pubkey_hash = recover(&message, signature)?;
+        let group = &mut self.groups[position];
+        if !group.config.pubkey_hashes.contains(&pubkey_hash) {
+            return Err(PartialTxError::UnknownSigner(pubkey_hash));
+        }
+        group
+            .signatures
+            .insert(pubkey_hash, to_signature(signature)?);
+        Ok(pubkey_hash)
+    }
+
+    /// Collects the signatures of `other`, which must sign the same transaction.
+    pub fn merge(&mut self, other: &PartialTx) -> Result<(), PartialTxError> {
+        let same_groups = self.groups.len() == other.groups.len()
+            && self
+                .groups
+                .iter()
+                .zip(&other.groups)
+                .all(|(a, b)| a.input_indices == b.input_indices && a.config == b.config);
+        if self.tx.witness_hash() != other.tx.witness_hash() || !same_groups {
+            return Err(PartialTxError::TxMismatch);
+        }
+        for (group, other_group) in self.groups.iter_mut().zip(&other.groups) {
+            for (pubkey_hash, signature) in &other_group.signatures {
+                group.signatures.entry(*pubkey_hash).or_insert(*signature);
+            }
+        }
+        Ok(())
+    }
+
+    /// Builds the signed transaction.
+    ///
+    /// The signatures of each group follow the order of the pubkeys in its config, starting
+    /// with the first `require_first_n` ones, so the lock matches each of them to its pubkey.
+    /// Exactly `threshold` signatures are kept.
+    pub fn finalize(&self) -> Result<TransactionView, PartialTxError> {
+        let mut witnesses: Vec<packed::Bytes> = self.tx.witnesses().into_iter().collect();
+        for group in &self.groups {
+            let first = group.input_indices[0];
+            let config = &group.config;
+            let mut lock = config.to_bytes().to_vec();
+            let mut count = 0;
+            for (index, pubkey_hash) in config.pubkey_hashes.iter().enumerate() {
+                if count == config.threshold {
+                    break;
+                }
+                match group.signatures.get(pubkey_hash) {
+                    Some(signature) => {
+                        lock.extend_from_slice(signature);
+                        count += 1;
+                    }
+                    None if index < usize::from(config.require_first_n) => {
+                        return Err(PartialTxError::MissingRequiredSignature(
+                            first,
+                            *pubkey_hash,
+                        ));
+                    }
+                    None => {}
+                }
+            }
+            if count < config.threshold {
+                return Err(PartialTxError::NotEnoughSignatures(first));
+            }
+
+            let witness_args = WitnessArgs::from_slice(&witnesses[first].raw_data())
+                .map_err(|_| MessageError::InvalidWitnessArgs(first))?;
+            witnesses[first] = witness_args
+                .as_builder()
+                .lock(Some(Bytes::from(lock)).pack())
+                .build()
+                .as_bytes()
+                .pack();
+        }
+        Ok(self
+            .tx
+            .as_advanced_builder()
+            .set_witnesses(witnesses)
+            .build())
+    }
+
+    /// Serializes the partially signed transaction.
+    ///
+    /// It is a molecule `BytesVec` of the `Transaction` followed by one item per group, which
+    /// is itself a `BytesVec` of the input indices as a `Uint32Vec`, the multisig script and
+    /// the signatures, each a pubkey hash followed by the signature.
+    pub fn to_bytes(&self) -> Bytes {
+        let mut items = vec![self.tx.data().as_bytes().pack()];
+        for group in &self.groups {
+            let indices: Vec<Uint32> = group
+                .input_indices
+                .iter()
+                .map(|index| (*index as u32).pack())
+                .collect();
+            let mut signatures = Vec::new();
+            for (pubkey_hash, signature) in &group.signatures {
+                signatures.extend_from_slice(pubkey_hash);
+                signatures.extend_from_slice(signature);
+            }
+            let group_items = vec![
+                Uint32Vec::new_builder()
+                    .set(indices)
+                    .build()
+                    .as_bytes()
+                    .pack(),
+                group.config.to_bytes().pack(),
+                Bytes::from(signatures).pack(),
+            ];
+            items.push(
+                BytesVec::new_builder()
+                    .set(group_items)
+                    .build()
+                    .as_bytes()
+                    .pack(),
+            );
+        }
+        BytesVec::new_builder().set(items).build().as_bytes()
+    }
+
+    /// Parses a partially signed transaction serialized by [`PartialTx::to_bytes`], verifying
+    /// its signatures.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, PartialTxError> {
+        let items = BytesVec::from_slice(bytes).map_err(|_| PartialTxError::InvalidFormat)?;
+        let tx = items
+            .get(0)
+            .and_then(|item| Transaction::from_slice(&item.raw_data()).ok())
+            .ok_or(PartialTxError::InvalidFormat)?
+            .into_view();
+
+        let mut partial = PartialTx {
+            tx,
+            groups: Vec::new(),
+        };
+        for item in items.into_iter().skip(1) {
+            let group_items = BytesVec::from_slice(&item.raw_data())
+                .map_err(|_| PartialTxError::InvalidFormat)?;
+            if group_items.len() != 3 {
+                return Err(PartialTxError::InvalidFormat);
+            }
+            let input_indices: Vec<usize> =
+                Uint32Vec::from_slice(&group_items.get_unchecked(0).raw_data())
+                    .map_err(|_| PartialTxError::InvalidFormat)?
+                    .into_iter()
+                    .map(|index| Unpack::<u32>::unpack(&index) as usize)
+                    .collect();
+            let inputs_len = partial.tx.inputs().len();
+            if input_indices.is_empty()
+                || input_indices.windows(2).any(|pair| pair[0] >= pair[1])
+                || input_indices[input_indices.len() - 1] >= inputs_len
+            {
+                return Err(PartialTxError::InvalidFormat);
+            }
+            let config = MultisigConfig::from_bytes(&group_items.get_unchecked(1).raw_data())
+                .map_err(|_| PartialTxError::InvalidFormat)?;
+            partial.groups.push(MultisigGroup {
+                input_indices,
+                config,
+                signatures: BTreeMap::new(),
+            });
+
+            let position = partial.groups.len() - 1;
+            let signatures = group_items.get_unchecked(2).raw_data();
+            if signatures.len() % (BLAKE160_SIZE + SIGNATURE_SIZE) != 0 {
+                return Err(PartialTxError::InvalidFormat);
+            }
+            for record in signatures.chunks_exact(BLAKE160_SIZE + SIGNATURE_SIZE) {
+                let pubkey_hash = partial.add_signature(position, &record[BLAKE160_SIZE..])?;
+                if pubkey_hash[..] != record[..BLAKE160_SIZE] {
+                    return Err(PartialTxError::InvalidSignature);
+                }
+            }
+        }
+        Ok(partial)
+    }
+}
+
+fn to_signature(bytes: &[u8]) -> Result<[u8; SIGNATURE_SIZE], PartialTxError> {
+    if bytes.len() != SIGNATURE_SIZE {
+        return Err(PartialTxError::InvalidSignature);
+    }
+    let mut signature = [0u8; SIGNATURE_SIZE];
+    signature.copy_from_slice(bytes);
+    Ok(signature)
+}
+
+/// Recovers the hash of the pubkey signing `message`.
+fn recover(message: &[u8; 32], signature: &[u8]) -> Result<[u8; BLAKE160_SIZE], PartialTxError> {
+    let signature = Signature::from_slice(&to_signature(signature)?)
+        .map_err(|_| PartialTxError::InvalidSignature)?;
+    let pubkey = signature
+        .recover(&H256::from(*message))
+        .map_err(|_| PartialTxError::InvalidSignature)?;
+    Ok(blake160(&pubkey.serialize()))
+}
@@ -0,0 +1,299 @@
+//! Exit codes of the bundled scripts.
+//!
+//! Each script defines its own codes, so the same number means different things in different
+//! scripts: `ERROR_ENCODING` is `-2` in `c/common.h` but `-11` in `c/dao.c`. The codes of a
+//! failed verification are decoded with [`decode_script_error`], which finds the script from
+//! the error itself.
+
+use crate::{
+    type_id::{
+        TYPE_HASH_DAO, TYPE_HASH_SECP256K1_BLAKE160_MULTISIG_ALL,
+        TYPE_HASH_SECP256K1_BLAKE160_SIGHASH_ALL,
+    },
+    versions::find_version,
+};
+use ckb_error::ErrorKind;
+use std::{error, fmt};
+
+const SIGHASH_NAME: &str = "secp256k1_blake160_sighash_all";
+const MULTISIG_NAME: &str = "secp256k1_blake160_multisig_all";
+const DAO_NAME: &str = "dao";
+
+macro_rules! exit_codes {
+    (
+        $(#[$meta:meta])*
+        pub enum $name:ident {
+            $(
+                #[doc = $doc:literal]
+                $variant:ident = $code:literal, $constant:literal, $text:literal;
+            )*
+        }
+    ) => {
+        $(#[$meta])*
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+        pub enum $name {
+            $(
+                #[doc = $doc]
+                $variant,
+            )*
+        }
+
+        impl $name {
+            /// The error of the exit code, `None` if the script doesn't define it.
+            pub fn from_exit_code(exit_code: i8) -> Option<Self> {
+                match exit_code {
+                    $($code => Some($name::$variant),)*
+                    _ => None,
+                }
+            }
+
+            /// Exit code of the script for this error.
+            pub fn exit_code(self) -> i8 {
+                match self {
+                    $($name::$variant => $code,)*
+                }
+            }
+
+            /// Name of the C constant defining the exit code.
+            pub fn name(self) -> &'static str {
+                match self {
+                    $($name::$variant => $constant,)*
+                }
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                let text = match self {
+                    $($name::$variant => $text,)*
+                };
+                write!(f, "{}", text)
+            }
+        }
+
+        impl error::Error for $name {}
+    };
+}
+
+exit_codes! {
+    /// Errors of the `secp256k1_blake160_sighash_all` lock, from `c/common.h` and
+    /// `c/secp256k1_helper.h`.
+    pub enum SighashError {
+        /// The lock args are not 20 bytes.
+        ArgumentsLen = -1, "ERROR_ARGUMENTS_LEN", "lock args must be 20 bytes";
+        /// A witness is not a valid `WitnessArgs`.
+        Encoding = -2, "ERROR_ENCODING", "invalid witness encoding";
+        /// A syscall failed.
+        Syscall = -3, "ERROR_SYSCALL", "syscall failed";
+        /// The pubkey can't be recovered from the signature.
+        SecpRecoverPubkey = -11, "ERROR_SECP_RECOVER_PUBKEY", "failed to recover the pubkey";
+        /// The signature is not a valid recoverable signature.
+        SecpParseSignature = -14, "ERROR_SECP_PARSE_SIGNATURE", "failed to parse the signature";
+        /// The recovered pubkey can't be serialized.
+        SecpSerializePubkey = -15, "ERROR_SECP_SERIALIZE_PUBKEY", "failed to serialize the pubkey";
+        /// The script is too long to be loaded.
+        ScriptTooLong = -21, "ERROR_SCRIPT_TOO_LONG", "script is too long";
+        /// The lock of the first witness is not a 65 bytes signature, or a witness is too long.
+        WitnessSize = -22, "ERROR_WITNESS_SIZE", "invalid witness size";
+        /// The blake160 hash of the recovered pubkey doesn't match the lock args.
+        PubkeyBlake160Hash = -31, "ERROR_PUBKEY_BLAKE160_HASH", "pubkey hash mismatches lock args";
+        /// The secp256k1 data cell can't be loaded.
+        LoadingData = -101, "CKB_SECP256K1_HELPER_ERROR_LOADING_DATA",
+            "failed to load secp256k1 data";
+        /// secp256k1 aborted on an illegal argument.
+        IllegalCallback = -102, "CKB_SECP256K1_HELPER_ERROR_ILLEGAL_CALLBACK",
+            "secp256k1 illegal callback";
+        /// secp256k1 aborted on an internal error.
+        ErrorCallback = -103, "CKB_SECP256K1_HELPER_ERROR_ERROR_CALLBACK",
+            "secp256k1 error callback";
+    }
+}
+
+exit_codes! {
+    /// Errors of the `secp256k1_blake160_multisig_all` lock, from `c/common.h`,
+    /// `c/secp256k1_helper.h` and the lock itself.
+    pub enum MultisigError {
+        /// The lock args are neither 20 nor 28 bytes.
+        ArgumentsLen = -1, "ERROR_ARGUMENTS_LEN", "lock args must be 20 or 28 bytes";
+        /// A witness is not a valid `WitnessArgs`.
+        Encoding = -2, "ERROR_ENCODING", "invalid witness encoding";
+        /// A syscall failed.
+        Syscall = -3, "ERROR_SYSCALL", "syscall failed";
+        /// The pubkey can't be recovered from a signature.
+        SecpRecoverPubkey = -11, "ERROR_SECP_RECOVER_PUBKEY", "failed to recover the pubkey";
+        /// A signature is not a valid recoverable signature.
+        SecpParseSignature = -14, "ERROR_SECP_PARSE_SIGNATURE", "failed to parse the signature";
+        /// A recovered pubkey can't be serialized.
+        SecpSerializePubkey = -15, "ERROR_SECP_SERIALIZE_PUBKEY", "failed to serialize the pubkey";
+        /// The script is too long to be loaded.
+        ScriptTooLong = -21, "ERROR_SCRIPT_TOO_LONG", "script is too long";
+        /// The lock of the first witness doesn't match the multisig script and threshold.
+        WitnessSize = -22, "ERROR_WITNESS_SIZE", "invalid witness size";
+        /// The flags of an input `since` differ from the lock args.
+        IncorrectSinceFlags = -23, "ERROR_INCORRECT_SINCE_FLAGS",
+            "input since flags mismatch lock args";
+        /// An input `since` is less than the lock args.
+        IncorrectSinceValue = -24, "ERROR_INCORRECT_SINCE_VALUE",
+            "input since is less than lock args";
+        /// The reserved `S` byte is not zero.
+        InvalidReserveField = -41, "ERROR_INVALID_RESERVE_FIELD", "reserved field is not zero";
+        /// There are no pubkeys.
+        InvalidPubkeysCnt = -42, "ERROR_INVALID_PUBKEYS_CNT", "invalid pubkeys count";
+        /// The threshold is zero or larger than the number of pubkeys.
+        InvalidThreshold = -43, "ERROR_INVALID_THRESHOLD", "invalid threshold";
+        /// `require_first_n` is larger than the threshold.
+        InvalidRequireFirstN = -44, "ERROR_INVALID_REQUIRE_FIRST_N", "invalid require_first_n";
+        /// The blake160 hash of the multisig script doesn't match the lock args.
+        MultisigScriptHash = -51, "ERROR_MULTSIG_SCRIPT_HASH",
+            "multisig script hash mismatches lock args";
+        /// The signatures don't match enough pubkeys.
+        Verification = -52, "ERROR_VERIFICATION", "signatures verification failed";
+        /// The secp256k1 data cell can't be loaded.
+        LoadingData = -101, "CKB_SECP256K1_HELPER_ERROR_LOADING_DATA",
+            "failed to load secp256k1 data";
+        /// secp256k1 aborted on an illegal argument.
+        IllegalCallback = -102, "CKB_SECP256K1_HELPER_ERROR_ILLEGAL_CALLBACK",
+            "secp256k1 illegal callback";
+        /// secp256k1 aborted on an internal error.
+        ErrorCallback = -103, "CKB_SECP256K1_HELPER_ERROR_ERROR_CALLBACK",
+            "secp256k1 error callback";
+    }
+}
+
+exit_codes! {
+    /// Errors of the NervosDAO type script, from `c/dao.c`, and the syscall errors from
+    /// `ckb_syscalls.h` which it returns as is.
+    pub enum DaoError {
+        /// A cell, witness or header dep is loaded out of bound.
+        IndexOutOfBound = 1, "CKB_INDEX_OUT_OF_BOUND", "index out of bound";
+        /// A cell has no type script, or the header of an input is not in the header deps.
+        ItemMissing = 2, "CKB_ITEM_MISSING", "item missing";
+        /// An unexpected error.
+        Unknown = -1, "ERROR_UNKNOWN", "unknown error";
+        /// The type script has args.
+        WrongNumberOfArguments = -2, "ERROR_WRONG_NUMBER_OF_ARGUMENTS",
+            "type script args must be empty";
+        /// A syscall failed.
+        Syscall = -4, "ERROR_SYSCALL", "syscall failed";
+        /// A loaded field is larger than the buffer.
+        BufferNotEnough = -10, "ERROR_BUFFER_NOT_ENOUGH", "buffer not enough";
+        /// A witness or cell data is not encoded as expected.
+        Encoding = -11, "ERROR_ENCODING", "invalid encoding";
+        /// A witness is too long to be loaded.
+        WitnessTooLong = -12, "ERROR_WITNESS_TOO_LONG", "witness is too long";
+        /// A capacity calculation overflows.
+        Overflow = -13, "ERROR_OVERFLOW", "capacity overflow";
+        /// The deposit or withdrawing block is wrong.
+        InvalidWithdrawBlock = -14, "ERROR_INVALID_WITHDRAW_BLOCK", "invalid withdraw block";
+        /// The outputs hold more than the maximum withdraw.
+        IncorrectCapacity = -15, "ERROR_INCORRECT_CAPACITY", "incorrect capacity";
+        /// An epoch in a header is invalid.
+        IncorrectEpoch = -16, "ERROR_INCORRECT_EPOCH", "incorrect epoch";
+        /// The `since` of a withdrawing input is less than the minimal lock period.
+        IncorrectSince = -17, "ERROR_INCORRECT_SINCE", "incorrect since";
+        /// A new DAO cell is not a deposit.
+        NewlyCreatedCell = -19, "ERROR_NEWLY_CREATED_CELL", "newly created cell is not a deposit";
+        /// A withdrawing cell doesn't match its deposit cell.
+        InvalidWithdrawingCell = -20, "ERROR_INVALID_WITHDRAWING_CELL", "invalid withdrawing cell";
+        /// The script is too long to be loaded.
+        ScriptTooLong = -21, "ERROR_SCRIPT_TOO_LONG", "script is too long";
+        /// The script iterated over too many cells.
+        MarkerExhausted = -30, "ERROR_MARKER_EXHAUSTED", "marker exhausted";
+    }
+}
+
+/// The decoded exit code of a bundled script.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SystemScriptError {
+    /// Exit code of `secp256k1_blake160_sighash_all`.
+    Sighash(SighashError),
+    /// Exit code of `secp256k1_blake160_multisig_all`.
+    Multisig(MultisigError),
+    /// Exit code of the NervosDAO type script.
+    Dao(DaoError),
+}
+
+impl SystemScriptError {
+    /// Exit code of the script for this error.
+    pub fn exit_code(self) -> i8 {
+        match self {
+            SystemScriptError::Sighash(error) => error.exit_code(),
+            SystemScriptError::Multisig(error) => error.exit_code(),
+            SystemScriptError::Dao(error) => error.exit_code(),
+        }
+    }
+
+    /// Name of the C constant defining the exit code.
+    pub fn name(self) -> &'static str {
+        match self {
+            SystemScriptError::Sighash(error) => error.name(),
+            SystemScriptError::Multisig(error) => error.name(),
+            SystemScriptError::Dao(error) => error.name(),
+        }
+    }
+
+    fn from_script_name(name: &str, exit_code: i8) -> Option<Self> {
+        match name {
+            SIGHASH_NAME => SighashError::from_exit_code(exit_code).map(SystemScriptError::Sighash),
+            MULTISIG_NAME => {
+                MultisigError::from_exit_code(exit_code).map(SystemScriptError::Multisig)
+            }
+            DAO_NAME => DaoError::from_exit_code(exit_code).map(SystemScriptError::Dao),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for SystemScriptError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SystemScriptError::Sighash(error) => write!(f, "{}: {}", SIGHASH_NAME, error),
+            SystemScriptError::Multisig(error) => write!(f, "{}: {}", MULTISIG_NAME, error),
+            SystemScriptError::Dao(error) => write!(f, "{}: {}", DAO_NAME, error),
+        }
+    }
+}
+
+impl error::Error for SystemScriptError {}
+
+/// Decodes the exit code of a script verification error.
+///
+/// The script is found from the hash in the `ValidationFailure` message: a data hash of a
+/// registered version, or the genesis type hash. `None` if the error is not a validation
+/// failure of a bundled script, or the exit code is not defined by the script.
+pub fn decode_script_error(error: &ckb_error::Error) -> Option<SystemScriptError> {
+    if error.kind() != ErrorKind::Script {
+        return None;
+    }
+    let message = error.to_string();
+    let (by, hash, exit_code) = parse_validation_failure(&message)?;
+    let name = match by {
+        "by-data-hash" => find_version(&hash)?.name,
+        "by-type-hash" if hash == TYPE_HASH_SECP256K1_BLAKE160_SIGHASH_ALL => SIGHASH_NAME,
+        "by-type-hash" if hash == TYPE_HASH_SECP256K1_BLAKE160_MULTISIG_ALL => MULTISIG_NAME,
+        "by-type-hash" if hash == TYPE_HASH_DAO => DAO_NAME,
+        _ => return None,
+    };
+    SystemScriptError::from_script_name(name, exit_code)
+}
+
+// Parses `.../ckb-script-error-codes/by-data-hash/<hash>.html#<exit code>`.
+fn parse_validation_failure(message: &str) -> Option<(&str, [u8; 32], i8)> {
+    const PAGE: &str = "ckb-script-error-codes/";
+    let path = &message[message.find(PAGE)? + PAGE.len()..];
+    let (by, path) = path.split_at(path.find('/')?);
+    let (hex, path) = path[1..].split_at(path[1..].find(".html#")?);
+    let exit_code = path[".html#".len()..]
+        .split(|c: char| c != '-' && !c.is_ascii_digit())
+        .next()?
+        .parse()
+        .ok()?;
+    if hex.len() != 64 || !hex.is_ascii() {
+        return None;
+    }
+    let mut hash = [0u8; 32];
+    for (i, byte) in hash.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16).ok()?;
+    }
+    Some((by, hash, exit_code))
+}
@@ -0,0 +1,166 @@
+//! Signing message of the `secp256k1_blake160_sighash_all` lock.
+//!
+//! The message is the blake2b hash of:
+//!
+//! 1. the transaction hash;
+//! 2. the witness of the first input in the script group, a `WitnessArgs` whose `lock` is
+//!    replaced by 65 zero bytes;
+//! 3. the witnesses of the other inputs in the group;
+//! 4. the witnesses whose index is not less than the number of inputs.
+//!
+//! Each witness is prefixed by its length as a 64-bit little endian integer.
+
+use ckb_hash::new_blake2b;
+use ckb_types::{
+    bytes::Bytes,
+    core::TransactionView,
+    packed::{self, WitnessArgs, WitnessArgsBuilder},
+    prelude::*,
+};
+use std::{error, fmt};
+
+/// Size of a recoverable secp256k1 signature, which is also the size of the `lock` field in the
+/// first witness of a sighash group.
+pub const SIGNATURE_SIZE: usize = 65;
+/// Witnesses larger than this are rejected by the lock.
+pub const MAX_WITNESS_SIZE: usize = 32768;
+/// Size of a blake160 hash, such as the pubkey hash in the lock args.
+pub const BLAKE160_SIZE: usize = 20;
+
+/// Errors when generating the signing message, the lock fails the transaction in these cases.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum MessageError {
+    /// The script group has no inputs.
+    EmptyGroup,
+    /// The input at this index has no witness.
+    MissingWitness(usize),
+    /// The witness at this index is not a valid `WitnessArgs`.
+    InvalidWitnessArgs(usize),
+    /// The witness at this index is larger than [`MAX_WITNESS_SIZE`].
+    WitnessTooLarge(usize),
+}
+
+impl fmt::Display for MessageError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MessageError::EmptyGroup => write!(f, "script group has no inputs"),
+            MessageError::MissingWitness(index) => write!(f, "witness {} is missing", index),
+            MessageError::InvalidWitnessArgs(index) => {
+                write!(f, "witness {} is not a valid WitnessArgs", index)
+            }
+            MessageError::WitnessTooLarge(index) => write!(
+                f,
+                "witness {} is larger than {} bytes",
+                index, MAX_WITNESS_SIZE
+            ),
+        }
+    }
+}
+
+impl error::Error for MessageError {}
+
+/// Generates the message signed by the `secp256k1_blake160_sighash_all` lock for the script
+/// group whose inputs are at `group_indices`, in ascending order.
+pub fn sighash_all_message(
+    tx: &TransactionView,
+    group_indices: &[usize],
+) -> Result<[u8; 32], MessageError> {
+    signing_message(tx, group_indices, Bytes::from(vec![0; SIGNATURE_SIZE]))
+}
+
+/// Generates the signing message with the `lock` of the first group witness replaced by
+/// `lock_for_digest`.
+pub(crate) fn signing_message(
+    tx: &TransactionView,
+    group_indices: &[usize],
+    lock_for_digest: Bytes,
+) -> Result<[u8; 32], MessageError> {
+    let first = *group_indices.first().ok_or(MessageError::EmptyGroup)?;
+    let witness = tx
+        .witnesses()
+        .get(first)
+        .ok_or(MessageError::MissingWitness(first))?
+        .raw_data();
+    if witness.len() > MAX_WITNESS_SIZE {
+        return Err(MessageError::WitnessTooLarge(first));
+    }
+    let witness_args =
+        WitnessArgs::from_slice(&witness).map_err(|_| MessageError::InvalidWitnessArgs(first))?;
+    let witness_for_digest = witness_args
+        .as_builder()
+        .lock(Some(lock_for_digest).pack())
+        .build();
+
+    let mut blake2b = new_blake2b();
+    blake2b.update(&tx.hash().raw_data());
+    blake2b.update(&(witness_for_digest.as_slice().len() as u64).to_le_bytes());
+    blake2b.update(witness_for_digest.as_slice());
+
+    // The lock stops at the first input without a witness, just as loading a witness out of
+    // bound ends its loops.
+    let other_witnesses = group_indices[1..]
+        .iter()
+        .copied()
+        .map_while(|index| tx.witnesses().get(index).map(|witness| (index, witness)));
+    let trailing_witnesses = (tx.inputs().len()..tx.witnesses().len())
+        .map(|index| (index, tx.witnesses().get_unchecked(index)));
+    for (index, witness) in other_witnesses.chain(trailing_witnesses) {
+        let witness = witness.raw_data();
+        if witness.len() > MAX_WITNESS_SIZE {
+            return Err(MessageError::WitnessTooLarge(index));
+        }
+        blake2b.update(&(witness.len() as u64).to_le_bytes());
+        blake2b.update(&witness);
+    }
+
+    let mut message = [0u8; 32];
+    blake2b.finalize(&mut message);
+    Ok(message)
+}
+
+/// The witnesses of a transaction being signed, with empty ones for the inputs missing them.
+#[derive(Clone)]
+pub(crate) struct InputWitnesses(Vec<packed::Bytes>);
+
+impl InputWitnesses {
+    /// The witnesses of `tx`, filled up to `inputs_len`.
+    pub(crate) fn new(tx: &TransactionView, inputs_len: usize) -> Self {
+        let mut witnesses: Vec<packed::Bytes> = tx.witnesses().into_iter().collect();
+        if witnesses.len() < inputs_len {
+            witnesses.resize(inputs_len, Bytes::new().pack());
+        }
+        InputWitnesses(witnesses)
+    }
+
+    /// Replaces the witness of the input at `index` by `update` applied to its `WitnessArgs`,
+    /// a default one when the witness is empty.
+    pub(crate) fn update_args<F>(&mut self, index: usize, update: F) -> Result<(), MessageError>
+    where
+        F: FnOnce(WitnessArgsBuilder) -> WitnessArgsBuilder,
+    {
+        let witness = &self.0[index];
+        let witness_args = if witness.is_empty() {
+            WitnessArgs::default()
+        } else {
+            WitnessArgs::from_slice(&witness.raw_data())
+                .map_err(|_| MessageError::InvalidWitnessArgs(index))?
+        };
+        self.0[index] = update(witness_args.as_builder()).build().as_bytes().pack();
+        Ok(())
+    }
+
+    /// `tx` with these witnesses.
+    pub(crate) fn set_to(&self, tx: &TransactionView) -> TransactionView {
+        tx.as_advanced_builder()
+            .set_witnesses(self.0.clone())
+            .build()
+    }
+}
+
+/// The blake160 hash of `data`, the first 20 bytes of its blake2b hash. The locks identify a
+/// compressed pubkey or a multisig script by it.
+pub fn blake160(data: &[u8]) -> [u8; BLAKE160_SIZE] {
+    let mut hash = [0u8; BLAKE160_SIZE];
+    hash.copy_from_slice(&ckb_hash::blake2b_256(data)[..BLAKE160_SIZE]);
+    hash
+}
@@ -0,0 +1,187 @@
+//! Signs transactions unlocked by `secp256k1_blake160_sighash_all`.
+//!
+//! A script group is every input sharing the same lock hash, wherever it sits in the
+//! transaction. The lock of each group reads its signature from the witness of the group's
+//! first input, which must be a `WitnessArgs`.
+
+#[cfg(feature = "sighash")]
+use crate::sighash::blake160;
+use crate::sighash::{sighash_all_message, InputWitnesses, MessageError, SIGNATURE_SIZE};
+#[cfg(any(feature = "sighash", feature = "multisig"))]
+use crate::SystemScript;
+#[cfg(feature = "sighash")]
+use ckb_crypto::secp::Privkey;
+#[cfg(any(feature = "sighash", feature = "multisig"))]
+use ckb_types::core::ScriptHashType;
+#[cfg(feature = "sighash")]
+use ckb_types::H256;
+use ckb_types::{
+    bytes::Bytes,
+    core::TransactionView,
+    packed::{Byte32, CellOutput, OutPoint, Script},
+    prelude::*,
+};
+#[cfg(any(feature = "sighash", feature = "multisig"))]
+use std::convert::TryFrom;
+use std::{collections::HashMap, error, fmt};
+
+/// Provides the cells spent by the transaction being signed.
+pub trait CellOutputProvider {
+    /// Returns the cell output pointed by `out_point`.
+    fn get_cell_output(&self, out_point: &OutPoint) -> Option<CellOutput>;
+}
+
+impl CellOutputProvider for HashMap<OutPoint, CellOutput> {
+    fn get_cell_output(&self, out_point: &OutPoint) -> Option<CellOutput> {
+        self.get(out_point).cloned()
+    }
+}
+
+/// Signs the messages of the script groups it owns.
+pub trait GroupSigner {
+    /// Whether the signer can unlock the group locked by `lock`.
+    fn owns(&self, lock: &Script) -> bool;
+
+    /// Signs `message` for the group locked by `lock`, `None` if signing fails.
+    fn sign(&self, lock: &Script, message: &[u8; 32]) -> Option<Bytes>;
+}
+
+/// Signs the groups locked by `secp256k1_blake160_sighash_all` whose args are the blake160
+/// hash of the public key.
+#[cfg(feature = "sighash")]
+impl GroupSigner for Privkey {
+    fn owns(&self, lock: &Script) -> bool {
+        match self.pubkey() {
+            Ok(pubkey) => {
+                is_sighash_lock(lock)
+                    && lock.args().raw_data()[..] == blake160(&pubkey.serialize())[..]
+            }
+            Err(_) => false,
+        }
+    }
+
+    fn sign(&self, _lock: &Script, message: &[u8; 32]) -> Option<Bytes> {
+        let signature = self.sign_recoverable(&H256::from(*message)).ok()?;
+        Some(Bytes::from(signature.serialize()))
+    }
+}
+
+/// Whether `lock` runs `secp256k1_blake160_sighash_all`, referenced either by data hash or by
+/// the genesis type hash.
+#[cfg(feature = "sighash")]
+pub fn is_sighash_lock(lock: &Script) -> bool {
+    runs_script(lock, SystemScript::Sighash)
+}
+
+/// Whether `script` runs the bundled binary of `system_script`.
+#[cfg(any(feature = "sighash", feature = "multisig"))]
+pub(crate) fn runs_script(script: &Script, system_script: SystemScript) -> bool {
+    let hash_type = match ScriptHashType::try_from(script.hash_type()) {
+        Ok(hash_type) => hash_type,
+        Err(_) => return false,
+    };
+    let code_hash: Byte32 = match system_script.code_hash(hash_type) {
+        Some(code_hash) => code_hash.pack(),
+        None => return false,
+    };
+    script.code_hash() == code_hash
+}
+
+/// Inputs of a transaction sharing the same lock script.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ScriptGroup {
+    /// The lock script of the inputs.
+    pub lock: Script,
+    /// Indices of the inputs in ascending order.
+    pub input_indices: Vec<usize>,
+}
+
+/// Errors when signing a transaction.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SignError {
+    /// The cell spent by the input at this index is not found.
+    InputCellNotFound(usize),
+    /// The signing message cannot be generated.
+    Message(MessageError),
+    /// The signer fails to sign the group whose first input is at this index.
+    SigningFailed(usize),
+}
+
+impl fmt::Display for SignError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SignError::InputCellNotFound(index) => {
+                write!(f, "cell spent by input {} is not found", index)
+            }
+            SignError::Message(err) => write!(f, "signing message: {}", err),
+            SignError::SigningFailed(index) => {
+                write!(f, "failed to sign the group of input {}", index)
+            }
+        }
+    }
+}
+
+impl error::Error for SignError {}
+
+impl From<MessageError> for SignError {
+    fn from(err: MessageError) -> Self {
+        SignError::Message(err)
+    }
+}
+
+/// Groups the inputs of `tx` by lock hash, ordered by the first input of each group.
+pub fn group_inputs<P: CellOutputProvider>(
+    tx: &TransactionView,
+    provider: &P,
+) -> Result<Vec<ScriptGroup>, SignError> {
+    let mut groups: Vec<ScriptGroup> = Vec::new();
+    let mut positions: HashMap<Byte32, usize> = HashMap::new();
+    for (index, input) in tx.inputs().into_iter().enumerate() {
+        let lock = provider
+            .get_cell_output(&input.previous_output())
+            .ok_or(SignError::InputCellNotFound(index))?
+            .lock();
+        let position = *positions.entry(lock.calc_script_hash()).or_insert_with(|| {
+            groups.push(ScriptGroup {
+                lock,
+                input_indices: Vec::new(),
+            });
+            groups.len() - 1
+        });
+        groups[position].input_indices.push(index);
+    }
+    Ok(groups)
+}
+
+/// Signs every script group of `tx` owned by `signer`.
+///
+/// Missing witnesses of the inputs are filled with empty ones, and an empty first witness of a
+/// signed group is replaced by a `WitnessArgs`. The witnesses of the groups not owned by the
+/// signer are left untouched.
+pub fn sign_tx<P: CellOutputProvider, S: GroupSigner>(
+    tx: &TransactionView,
+    provider: &P,
+    signer: &S,
+) -> Result<TransactionView, SignError> {
+    let groups = group_inputs(tx, provider)?;
+    let mut witnesses = InputWitnesses::new(tx, tx.inputs().len());
+
+    let mut tx = tx.clone();
+    for group in groups {
+        if !signer.owns(&group.lock) {
+            continue;
+        }
+        let first = group.input_indices[0];
+        witnesses.update_args(first, |builder| {
+            builder.lock(Some(Bytes::from(vec![0; SIGNATURE_SIZE])).pack())
+        })?;
+        tx = witnesses.set_to(&tx);
+
+        let message = sighash_all_message(&tx, &group.input_indices)?;
+        let signature = signer
+            .sign(&group.lock, &message)
+            .ok_or(SignError::SigningFailed(first))?;
+        witnesses.update_args(first, |builder| builder.lock(Some(signature).pack()))?;
+    }
+    Ok(witnesses.set_to(&tx))
+}
@@ -0,0 +1,227 @@
+//! The `since` field of a transaction input, as understood by the system scripts.
+//!
+//! The 8 most significant bits are flags and the remaining 56 bits are the value:
+//!
+//! ```text
+//! relative | metric (2 bits) | reserved (5 bits) | value (56 bits)
+//! ```
+//!
+//! The metric is a block number (`00`), an epoch number with fraction (`01`) or a median
+//! timestamp in seconds (`10`). The locks compare an input `since` with a constraint in
+//! `check_since` of `c/common.h`, which [`Since::satisfies`] and [`Since::cmp_since`]
+//! follow.
+
+use std::cmp::Ordering;
+
+/// Number of bits of the value.
+pub const SINCE_VALUE_BITS: u32 = 56;
+/// Mask of the value.
+pub const SINCE_VALUE_MASK: u64 = 0x00ff_ffff_ffff_ffff;
+/// Flags of an absolute epoch, whose value is compared as an epoch number with fraction.
+pub const SINCE_EPOCH_FRACTION_FLAG: u8 = 0b0010_0000;
+
+const RELATIVE_FLAG: u8 = 0b1000_0000;
+const METRIC_MASK: u8 = 0b0110_0000;
+const RESERVED_MASK: u8 = 0b0001_1111;
+const METRIC_BLOCK_NUMBER: u8 = 0b0000_0000;
+const METRIC_EPOCH: u8 = 0b0010_0000;
+const METRIC_TIMESTAMP: u8 = 0b0100_0000;
+
+/// An epoch number with the fraction of the epoch elapsed, `index / length`.
+///
+/// Like `epoch_number_with_fraction_cmp` of `c/utils.h`, [`EpochNumberWithFraction::cmp_epoch`]
+/// orders epochs by the number and then by the fraction, so `1/2` and `2/4` of the same epoch
+/// compare equal although their values differ. Equality is on the packed value, which is why
+/// the type doesn't implement `Ord`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct EpochNumberWithFraction(u64);
+
+impl EpochNumberWithFraction {
+    /// Number of bits of the epoch number.
+    pub const NUMBER_BITS: u32 = 24;
+    /// Number of bits of the index and of the length.
+    pub const FRACTION_BITS: u32 = 16;
+
+    /// Creates an epoch, `None` if one of the parts overflows its bits or `index` is not
+    /// less than a non-zero `length`.
+    pub fn new(number: u64, index: u64, length: u64) -> Option<Self> {
+        if number >> Self::NUMBER_BITS != 0
+            || index >> Self::FRACTION_BITS != 0
+            || length >> Self::FRACTION_BITS != 0
+            || (length > 0 && index >= length)
+        {
+            return None;
+        }
+        Some(EpochNumberWithFraction(
+            (length << (Self::NUMBER_BITS + Self::FRACTION_BITS))
+                | (index << Self::NUMBER_BITS)
+                | number,
+        ))
+    }
+
+    /// Reads an epoch from the value of a `since`, ignoring the bits above 56.
+    pub const fn from_full_value(value: u64) -> Self {
+        EpochNumberWithFraction(value & SINCE_VALUE_MASK)
+    }
+
+    /// The epoch packed in 56 bits, `length | index | number`.
+    pub const fn full_value(self) -> u64 {
+        self.0
+    }
+
+    /// The epoch number.
+    pub const fn number(self) -> u64 {
+        self.0 & ((1 << Self::NUMBER_BITS) - 1)
+    }
+
+    /// The index of the block in the epoch.
+    pub const fn index(self) -> u64 {
+        (self.0 >> Self::NUMBER_BITS) & ((1 << Self::FRACTION_BITS) - 1)
+    }
+
+    /// The number of blocks of the epoch.
+    pub const fn length(self) -> u64 {
+        (self.0 >> (Self::NUMBER_BITS + Self::FRACTION_BITS)) & ((1 << Self::FRACTION_BITS) - 1)
+    }
+
+    /// Compares the numbers, then the fractions by cross multiplication.
+    pub fn cmp_epoch(self, other: Self) -> Ordering {
+        self.number()
+            .cmp(&other.number())
+            .then_with(|| (self.index() * other.length()).cmp(&(other.index() * self.length())))
+    }
+}
+
+/// What the value of a `since` measures.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SinceMetric {
+    /// A block number.
+    BlockNumber(u64),
+    /// An epoch number with fraction.
+    EpochNumberWithFraction(EpochNumberWithFraction),
+    /// A median timestamp of the previous blocks, in seconds.
+    Timestamp(u64),
+}
+
+/// A raw `since` value.
+///
+/// Two `since` are only comparable when their flags are the same, absolute epochs are
+/// compared with [`EpochNumberWithFraction::cmp_epoch`] and the others by the raw value,
+/// exactly like `check_since`. Note that this makes relative epochs compare by their packed
+/// value.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Since(u64);
+
+impl Since {
+    /// Creates a `since` measured by `metric`, `None` if a block number or timestamp
+    /// overflows 56 bits.
+    pub fn new(metric: SinceMetric, relative: bool) -> Option<Self> {
+        let (metric_flag, value) = match metric {
+            SinceMetric::BlockNumber(number) => (METRIC_BLOCK_NUMBER, number),
+            SinceMetric::EpochNumberWithFraction(epoch) => (METRIC_EPOCH, epoch.full_value()),
+            SinceMetric::Timestamp(timestamp) => (METRIC_TIMESTAMP, timestamp),
+        };
+        if value & !SINCE_VALUE_MASK != 0 {
+            return None;
+        }
+        let flags = if relative {
+            RELATIVE_FLAG | metric_flag
+        } else {
+            metric_flag
+        };
+        Some(Since((u64::from(flags) << SINCE_VALUE_BITS) | value))
+    }
+
+    /// An absolute block number.
+    pub fn absolute_block_number(number: u64) -> Option<Self> {
+        Self::new(SinceMetric::BlockNumber(number), false)
+    }
+
+    /// A number of blocks after the input cell is committed.
+    pub fn relative_block_number(number: u64) -> Option<Self> {
+        Self::new(SinceMetric::BlockNumber(number), true)
+    }
+
+    /// An absolute epoch.
+    pub fn absolute_epoch(epoch: EpochNumberWithFraction) -> Self {
+        Since((u64::from(METRIC_EPOCH) << SINCE_VALUE_BITS) | epoch.full_value())
+    }
+
+    /// A number of epochs after the input cell is committed.
+    pub fn relative_epoch(epoch: EpochNumberWithFraction) -> Self {
+        Since((u64::from(RELATIVE_FLAG | METRIC_EPOCH) << SINCE_VALUE_BITS) | epoch.full_value())
+    }
+
+    /// An absolute median timestamp in seconds.
+    pub fn absolute_timestamp(timestamp: u64) -> Option<Self> {
+        Self::new(SinceMetric::Timestamp(timestamp), false)
+    }
+
+    /// A number of seconds after the input cell is committed.
+    pub fn relative_timestamp(timestamp: u64) -> Option<Self> {
+        Self::new(SinceMetric::Timestamp(timestamp), true)
+    }
+
+    /// Wraps a raw `since` value.
+    pub const fn from_raw_value(raw: u64) -> Self {
+        Since(raw)
+    }
+
+    /// The raw `since` value.
+    pub const fn as_u64(self) -> u64 {
+        self.0
+    }
+
+    /// The 8 flag bits.
+    pub const fn flags(self) -> u8 {
+        (self.0 >> SINCE_VALUE_BITS) as u8
+    }
+
+    /// The 56 value bits.
+    pub const fn value(self) -> u64 {
+        self.0 & SINCE_VALUE_MASK
+    }
+
+    /// Whether the value is relative to the block committing the input cell.
+    pub const fn is_relative(self) -> bool {
+        self.flags() & RELATIVE_FLAG != 0
+    }
+
+    /// The metric and value, `None` if the reserved bits are set or the metric is unknown.
+    pub fn metric(self) -> Option<SinceMetric> {
+        if self.flags() & RESERVED_MASK != 0 {
+            return None;
+        }
+        match self.flags() & METRIC_MASK {
+            METRIC_BLOCK_NUMBER => Some(SinceMetric::BlockNumber(self.value())),
+            METRIC_EPOCH => Some(SinceMetric::EpochNumberWithFraction(
+                EpochNumberWithFraction::from_full_value(self.value()),
+            )),
+            METRIC_TIMESTAMP => Some(SinceMetric::Timestamp(self.value())),
+            _ => None,
+        }
+    }
+
+    /// Whether an input with this `since` passes the `check_since` of `constraint`: the flags
+    /// are the same, and the value is not less than the constraint.
+    pub fn satisfies(self, constraint: Since) -> bool {
+        self.cmp_since(constraint)
+            .map(|ordering| ordering != Ordering::Less)
+            .unwrap_or(false)
+    }
+
+    /// Compares with `other`, `None` if the flags differ.
+    pub fn cmp_since(self, other: Since) -> Option<Ordering> {
+        if self.flags() != other.flags() {
+            return None;
+        }
+        if self.flags() == SINCE_EPOCH_FRACTION_FLAG {
+            Some(
+                EpochNumberWithFraction::from_full_value(self.value())
+                    .cmp_epoch(EpochNumberWithFraction::from_full_value(other.value())),
+            )
+        } else {
+            Some(self.value().cmp(&other.value()))
+        }
+    }
+}
@@ -0,0 +1,172 @@
+use crate::BUNDLED_CELL;
+#[cfg(feature = "secp256k1-data")]
+use crate::CODE_HASH_SECP256K1_DATA;
+#[cfg(feature = "dao")]
+use crate::{CODE_HASH_DAO, ELF_METADATA_DAO};
+#[cfg(feature = "multisig")]
+use crate::{
+    CODE_HASH_SECP256K1_BLAKE160_MULTISIG_ALL, ELF_METADATA_SECP256K1_BLAKE160_MULTISIG_ALL,
+};
+#[cfg(feature = "sighash")]
+use crate::{
+    CODE_HASH_SECP256K1_BLAKE160_SIGHASH_ALL, ELF_METADATA_SECP256K1_BLAKE160_SIGHASH_ALL,
+};
+use ckb_types::bytes::Bytes;
+use includedir::Compression;
+use lazy_static::lazy_static;
+use std::{borrow::Cow, collections::HashMap, fmt, sync::Mutex};
+
+lazy_static! {
+    static ref DECOMPRESSED_BINARIES: Mutex<HashMap<SystemScript, Bytes>> =
+        Mutex::new(HashMap::new());
+}
+
+/// Metadata extracted from a bundled RISC-V ELF binary at build time.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ElfMetadata {
+    /// Total size of the executable sections in bytes.
+    pub text_size: u64,
+    /// Entry point address.
+    pub entry_point: u64,
+    /// Whether the binary has neither a symbol table nor debug sections.
+    pub stripped: bool,
+}
+
+/// System scripts bundled in this crate.
+///
+/// Each variant only exists when the cargo feature bundling its binary is enabled.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SystemScript {
+    /// `secp256k1_blake160_sighash_all` lock
+    #[cfg(feature = "sighash")]
+    Sighash,
+    /// `secp256k1_blake160_multisig_all` lock
+    #[cfg(feature = "multisig")]
+    Multisig,
+    /// NervosDAO type script
+    #[cfg(feature = "dao")]
+    Dao,
+    /// secp256k1 precomputed table required by the secp256k1 locks
+    #[cfg(feature = "secp256k1-data")]
+    Secp256k1Data,
+}
+
+impl SystemScript {
+    /// All bundled system scripts, in the order they are listed in `specs/manifest.toml`.
+    pub const ALL: &'static [SystemScript] = &[
+        #[cfg(feature = "sighash")]
+        SystemScript::Sighash,
+        #[cfg(feature = "secp256k1-data")]
+        SystemScript::Secp256k1Data,
+        #[cfg(feature = "dao")]
+        SystemScript::Dao,
+        #[cfg(feature = "multisig")]
+        SystemScript::Multisig,
+    ];
+
+    /// Iterates over every bundled system script.
+    pub fn iter() -> impl Iterator<Item = SystemScript> {
+        Self::ALL.iter().copied()
+    }
+
+    /// Finds a system script by its binary name, e.g. `"dao"`.
+    pub fn from_name(name: &str) -> Option<SystemScript> {
+        Self::iter().find(|script| script.name() == name)
+    }
+
+    /// Binary name of the script, as listed in `specs/manifest.toml`.
+    pub fn name(self) -> &'static str {
+        match self {
+            #[cfg(feature = "sighash")]
+            SystemScript::Sighash => "secp256k1_blake160_sighash_all",
+            #[cfg(feature = "multisig")]
+            SystemScript::Multisig => "secp256k1_blake160_multisig_all",
+            #[cfg(feature = "dao")]
+            SystemScript::Dao => "dao",
+            #[cfg(feature = "secp256k1-data")]
+            SystemScript::Secp256k1Data => "secp256k1_data",
+        }
+    }
+
+    /// Path of the binary in `BUNDLED_CELL`.
+    pub fn path(self) -> &'static str {
+        match self {
+            #[cfg(feature = "sighash")]
+            SystemScript::Sighash => "specs/cells/secp256k1_blake160_sighash_all",
+            #[cfg(feature = "multisig")]
+            SystemScript::Multisig => "specs/cells/secp256k1_blake160_multisig_all",
+            #[cfg(feature = "dao")]
+            SystemScript::Dao => "specs/cells/dao",
+            #[cfg(feature = "secp256k1-data")]
+            SystemScript::Secp256k1Data => "specs/cells/secp256k1_data",
+        }
+    }
+
+    /// Blake2b hash of the binary, which is the `code_hash` when referenced by `hash_type: data`.
+    pub fn data_hash(self) -> [u8; 32] {
+        match self {
+            #[cfg(feature = "sighash")]
+            SystemScript::Sighash => CODE_HASH_SECP256K1_BLAKE160_SIGHASH_ALL,
+            #[cfg(feature = "multisig")]
+            SystemScript::Multisig => CODE_HASH_SECP256K1_BLAKE160_MULTISIG_ALL,
+            #[cfg(feature = "dao")]
+            SystemScript::Dao => CODE_HASH_DAO,
+            #[cfg(feature = "secp256k1-data")]
+            SystemScript::Secp256k1Data => CODE_HASH_SECP256K1_DATA,
+        }
+    }
+
+    /// ELF metadata of the binary, `None` for `secp256k1_data` which is not an executable.
+    pub fn elf_metadata(self) -> Option<ElfMetadata> {
+        match self {
+            #[cfg(feature = "sighash")]
+            SystemScript::Sighash => Some(ELF_METADATA_SECP256K1_BLAKE160_SIGHASH_ALL),
+            #[cfg(feature = "multisig")]
+            SystemScript::Multisig => Some(ELF_METADATA_SECP256K1_BLAKE160_MULTISIG_ALL),
+            #[cfg(feature = "dao")]
+            SystemScript::Dao => Some(ELF_METADATA_DAO),
+            #[cfg(feature = "secp256k1-data")]
+            SystemScript::Secp256k1Data => None,
+        }
+    }
+
+    /// Binary content of the script, decompressed on every call. Use [`get_binary`] for
+    /// repeated access.
+    pub fn binary(self) -> Cow<'static, [u8]> {
+        BUNDLED_CELL
+            .get(self.path())
+            .expect("bundled system script")
+    }
+
+    /// Size of the binary in bytes.
+    pub fn size(self) -> usize {
+        get_binary(self).len()
+    }
+}
+
+/// Returns the binary content of `script`.
+///
+/// Gzip compressed binaries are decompressed on the first access and cached for the lifetime
+/// of the process. When the crate is built with the `uncompressed` feature the returned bytes
+/// point to the static data directly without copying.
+pub fn get_binary(script: SystemScript) -> Bytes {
+    let (compression, data) = BUNDLED_CELL
+        .files
+        .get(script.path())
+        .expect("bundled system script");
+    if *compression == Compression::None {
+        return Bytes::from_static(data);
+    }
+
+    let mut binaries = DECOMPRESSED_BINARIES.lock().expect("lock binaries cache");
+    binaries
+        .entry(script)
+        .or_insert_with(|| Bytes::from(script.binary().into_owned()))
+        .clone()
+}
+
+impl fmt::Display for SystemScript {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
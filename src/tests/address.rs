@@ -0,0 +1,143 @@
+use crate::{
+    address::{Address, AddressError, AddressPayload, CodeIndex, Network},
+    TYPE_HASH_SECP256K1_BLAKE160_MULTISIG_ALL, TYPE_HASH_SECP256K1_BLAKE160_SIGHASH_ALL,
+};
+use ckb_types::{bytes::Bytes, core::ScriptHashType, packed::Script, prelude::*};
+use std::str::FromStr;
+
+fn hex(data: &str) -> Bytes {
+    let mut bytes = vec![0; data.len() / 2];
+    faster_hex::hex_decode(data.as_bytes(), &mut bytes).unwrap();
+    bytes.into()
+}
+
+fn lock(code_hash: [u8; 32], hash_type: ScriptHashType, args: Bytes) -> Script {
+    Script::new_builder()
+        .code_hash(code_hash.pack())
+        .hash_type(hash_type.into())
+        .args(args.pack())
+        .build()
+}
+
+fn check_round_trip(address: &Address, expected: &str) {
+    assert_eq!(address.to_string(), expected);
+    assert_eq!(&Address::from_str(expected).unwrap(), address);
+}
+
+#[test]
+fn test_sighash_address() {
+    let args = hex("b39bbc0b3673c7d36450bc14cfcdad2d559c6c64");
+    let sighash_lock = lock(
+        TYPE_HASH_SECP256K1_BLAKE160_SIGHASH_ALL,
+        ScriptHashType::Type,
+        args,
+    );
+
+    let short = Address::new_short(Network::Mainnet, &sighash_lock).unwrap();
+    check_round_trip(&short, "ckb1qyqt8xaupvm8837nv3gtc9x0ekkj64vud3jqfwyw5v");
+    assert_eq!(short.script(), sighash_lock);
+
+    let full = Address::new(Network::Mainnet, sighash_lock.clone());
+    check_round_trip(
+        &full,
+        "ckb1qzda0cr08m85hc8jlnfp3zer7xulejywt49kt2rr0vthywaa50xwsqdnnw7qkdnnclfkg59uzn8umtfd2kwxceqxwquc4",
+    );
+    assert_eq!(full.script(), sighash_lock);
+
+    let testnet = Address::new(Network::Testnet, sighash_lock);
+    assert!(testnet.to_string().starts_with("ckt1"));
+    assert_eq!(Address::from_str(&testnet.to_string()), Ok(testnet));
+}
+
+#[test]
+fn test_multisig_address() {
+    let args = hex("4fb2be2e5d0c1a3b8694f832350a33c1685d477a");
+    let multisig_lock = lock(
+        TYPE_HASH_SECP256K1_BLAKE160_MULTISIG_ALL,
+        ScriptHashType::Type,
+        args,
+    );
+    let short = Address::new_short(Network::Mainnet, &multisig_lock).unwrap();
+    check_round_trip(&short, "ckb1qyq5lv479ewscx3ms620sv34pgeuz6zagaaqklhtgg");
+    match &short.payload {
+        AddressPayload::Short { code_index, .. } => assert_eq!(*code_index, CodeIndex::Multisig),
+        _ => panic!("short format expected"),
+    }
+    assert_eq!(short.script(), multisig_lock);
+
+    // args with since only fit in the full format
+    let mut args_with_since = multisig_lock.args().raw_data().to_vec();
+    args_with_since.extend_from_slice(&[0; 8]);
+    let time_locked = multisig_lock
+        .as_builder()
+        .args(Bytes::from(args_with_since).pack())
+        .build();
+    assert_eq!(Address::new_short(Network::Mainnet, &time_locked), None);
+    let full = Address::new(Network::Mainnet, time_locked);
+    assert_eq!(Address::from_str(&full.to_string()), Ok(full));
+}
+
+#[test]
+fn test_full_address_hash_types() {
+    let args = hex("b39bbc0b3673c7d36450bc14cfcdad2d559c6c64");
+    let cases = vec![
+        (
+            ScriptHashType::Data,
+            "ckb1qzda0cr08m85hc8jlnfp3zer7xulejywt49kt2rr0vthywaa50xwsq9nnw7qkdnnclfkg59uzn8umtfd2kwxceqvguktl",
+        ),
+        (
+            ScriptHashType::Data1,
+            "ckb1qzda0cr08m85hc8jlnfp3zer7xulejywt49kt2rr0vthywaa50xwsq4nnw7qkdnnclfkg59uzn8umtfd2kwxceqcydzyt",
+        ),
+    ];
+    for (hash_type, expected) in cases {
+        let data_lock = lock(
+            TYPE_HASH_SECP256K1_BLAKE160_SIGHASH_ALL,
+            hash_type,
+            args.clone(),
+        );
+        assert_eq!(Address::new_short(Network::Mainnet, &data_lock), None);
+        check_round_trip(&Address::new(Network::Mainnet, data_lock), expected);
+    }
+}
+
+#[test]
+fn test_invalid_address() {
+    // the short format with a bech32m checksum
+    let short = Address::from_str("ckb1qyqt8xaupvm8837nv3gtc9x0ekkj64vud3jqfwyw5v").unwrap();
+    let bech32m = bech32::encode(
+        "ckb",
+        bech32::ToBase32::to_base32(&hex("0100b39bbc0b3673c7d36450bc14cfcdad2d559c6c64")),
+        bech32::Variant::Bech32m,
+    )
+    .unwrap();
+    assert_eq!(
+        Address::from_str(&bech32m),
+        Err(AddressError::InvalidVariant)
+    );
+    let unknown_prefix = short.to_string().replacen("ckb", "ckc", 1);
+    assert_eq!(
+        Address::from_str(&unknown_prefix),
+        Err(AddressError::InvalidEncoding)
+    );
+    let unknown_index = bech32::encode(
+        "ckt",
+        bech32::ToBase32::to_base32(&hex("0102b39bbc0b3673c7d36450bc14cfcdad2d559c6c64")),
+        bech32::Variant::Bech32,
+    )
+    .unwrap();
+    assert_eq!(
+        Address::from_str(&unknown_index),
+        Err(AddressError::UnknownCodeIndex(2))
+    );
+    let other_prefix = bech32::encode(
+        "ckc",
+        bech32::ToBase32::to_base32(&hex("0100b39bbc0b3673c7d36450bc14cfcdad2d559c6c64")),
+        bech32::Variant::Bech32,
+    )
+    .unwrap();
+    assert_eq!(
+        Address::from_str(&other_prefix),
+        Err(AddressError::UnknownPrefix("ckc".to_string()))
+    );
+}
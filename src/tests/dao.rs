@@ -1,4 +1,13 @@
 use super::{sign_tx, DummyDataLoader, DAO_BIN, MAX_CYCLES, SECP256K1_DATA_BIN, SIGHASH_ALL_BIN};
+use crate::{
+    dao::{
+        calculate_maximum_withdraw, deposit, is_withdrawable, minimal_withdraw_since,
+        withdraw_phase1, withdraw_phase2, DaoBuildError,
+    },
+    script_error::{decode_script_error, DaoError, SystemScriptError},
+    since::{self, Since},
+    verifier::verify_dao,
+};
 use byteorder::{ByteOrder, LittleEndian};
 use ckb_crypto::secp::{Generator, Privkey};
 use ckb_dao_utils::pack_dao_data;
@@ -15,16 +24,9 @@ use ckb_types::{
     packed::{Byte32, CellDep, CellInput, CellOutput, OutPoint, Script, WitnessArgs},
     prelude::*,
 };
-use rand::{thread_rng, Rng};
+use rand::{rngs::SmallRng, thread_rng, Rng, SeedableRng};
 use std::sync::Arc;
 
-const ERROR_SYSCALL: i8 = -4;
-const ERROR_INVALID_WITHDRAW_BLOCK: i8 = -14;
-const ERROR_INCORRECT_CAPACITY: i8 = -15;
-const ERROR_INCORRECT_SINCE: i8 = -17;
-const ERROR_NEWLY_CREATED_CELL: i8 = -19;
-const ERROR_INVALID_WITHDRAWING_CELL: i8 = -20;
-
 fn cell_output_with_only_capacity(shannons: u64) -> CellOutput {
     CellOutput::new_builder()
         .capacity(Capacity::shannons(shannons).pack())
@@ -944,7 +946,7 @@ fn test_dao_invalid_deposit_header() {
         verify_result.unwrap_err(),
         ScriptError::validation_failure(
             &cell.type_().to_opt().unwrap(),
-            ERROR_INVALID_WITHDRAW_BLOCK
+            DaoError::InvalidWithdrawBlock.exit_code()
         )
         .input_type_script(0),
     );
@@ -1018,8 +1020,11 @@ fn test_dao_invalid_withdraw_amount() {
     let verify_result = TransactionScriptsVerifier::new(rtx, data_loader).verify(MAX_CYCLES);
     assert_error_eq!(
         verify_result.unwrap_err(),
-        ScriptError::validation_failure(&cell.type_().to_opt().unwrap(), ERROR_INCORRECT_CAPACITY)
-            .input_type_script(0),
+        ScriptError::validation_failure(
+            &cell.type_().to_opt().unwrap(),
+            DaoError::IncorrectCapacity.exit_code()
+        )
+        .input_type_script(0),
     );
 }
 
@@ -1092,8 +1097,11 @@ fn test_dao_invalid_since() {
     let verify_result = TransactionScriptsVerifier::new(rtx, data_loader).verify(MAX_CYCLES);
     assert_error_eq!(
         verify_result.unwrap_err(),
-        ScriptError::validation_failure(&cell.type_().to_opt().unwrap(), ERROR_INCORRECT_SINCE)
-            .input_type_script(0),
+        ScriptError::validation_failure(
+            &cell.type_().to_opt().unwrap(),
+            DaoError::IncorrectSince.exit_code()
+        )
+        .input_type_script(0),
     );
 }
 
@@ -1266,7 +1274,7 @@ fn test_dao_deposit_invalid_cell() {
         verify_result.unwrap_err(),
         ScriptError::validation_failure(
             &output_cell.type_().to_opt().unwrap(),
-            ERROR_NEWLY_CREATED_CELL
+            DaoError::NewlyCreatedCell.exit_code()
         )
         .output_type_script(0),
     );
@@ -1320,8 +1328,11 @@ fn test_dao_deposit_cell_missing_data() {
     let verify_result = TransactionScriptsVerifier::new(rtx, data_loader).verify(MAX_CYCLES);
     assert_error_eq!(
         verify_result.unwrap_err(),
-        ScriptError::validation_failure(&output_cell.type_().to_opt().unwrap(), ERROR_SYSCALL)
-            .output_type_script(0),
+        ScriptError::validation_failure(
+            &output_cell.type_().to_opt().unwrap(),
+            DaoError::Syscall.exit_code()
+        )
+        .output_type_script(0),
     );
 }
 
@@ -1582,7 +1593,7 @@ fn test_dao_create_withdrawing_cell_with_invalid_data() {
         verify_result.unwrap_err(),
         ScriptError::validation_failure(
             &cell.type_().to_opt().unwrap(),
-            ERROR_INVALID_WITHDRAWING_CELL
+            DaoError::InvalidWithdrawingCell.exit_code()
         )
         .input_type_script(0),
     );
@@ -1649,7 +1660,7 @@ fn test_dao_create_withdrawing_cell_with_invalid_capacity() {
         verify_result.unwrap_err(),
         ScriptError::validation_failure(
             &cell.type_().to_opt().unwrap(),
-            ERROR_INVALID_WITHDRAWING_CELL
+            DaoError::InvalidWithdrawingCell.exit_code()
         )
         .input_type_script(0),
     );
@@ -1830,3 +1841,530 @@ fn test_dao_all_dao_actions() {
     let verify_result = TransactionScriptsVerifier::new(rtx, data_loader).verify(MAX_CYCLES);
     verify_result.expect("pass verification");
 }
+
+fn verify_natively_and_in_vm(
+    data_loader: &DummyDataLoader,
+    rtx: ResolvedTransaction,
+    dao_script: &Script,
+) -> Result<(), DaoError> {
+    let native_result = verify_dao(&rtx, dao_script, data_loader);
+    let verify_result =
+        TransactionScriptsVerifier::new(Arc::new(rtx), data_loader.clone()).verify(MAX_CYCLES);
+    match native_result {
+        Ok(()) => {
+            verify_result.expect("pass verification");
+        }
+        Err(err) => assert_eq!(
+            decode_script_error(&verify_result.unwrap_err()),
+            Some(SystemScriptError::Dao(err))
+        ),
+    }
+    native_result
+}
+
+#[test]
+fn test_verify_dao_matches_vm() {
+    let mut rng = SmallRng::seed_from_u64(22);
+    let mut data_loader = DummyDataLoader::new();
+    let (privkey, lock_args) = gen_lock();
+    let (deposit_header, deposit_epoch) = gen_header(1554, 10000000, 35, 1000, 1000);
+    let (withdraw_header, withdraw_epoch) = gen_header(2000610, 10001000, 575, 2000000, 1100);
+    for (header, epoch) in [
+        (&deposit_header, deposit_epoch),
+        (&withdraw_header, withdraw_epoch),
+    ] {
+        data_loader.headers.insert(header.hash(), header.clone());
+        data_loader.epoches.insert(header.hash(), epoch);
+    }
+
+    let mut errors = Vec::new();
+    for _ in 0..48 {
+        let (cell, previous_out_point) = gen_dao_cell(
+            &mut data_loader,
+            Capacity::shannons(123456780000),
+            lock_args.clone(),
+        );
+        let dao_script = cell.type_().to_opt().unwrap();
+        let mut builder = TransactionBuilder::default();
+        let input_cell_meta = if rng.gen() {
+            // Phase 1, the deposited cell is withdrawn by a cell at the same index.
+            let block_number = deposit_header.number() + rng.gen_range(0, 2);
+            let output_data = match rng.gen_range(0, 4) {
+                0 => Bytes::from(vec![0; 4]),
+                1 => Bytes::from(vec![0; 8]),
+                _ => Bytes::from(block_number.to_le_bytes().to_vec()),
+            };
+            let (output_cell, _) = gen_dao_cell(
+                &mut data_loader,
+                Capacity::shannons(123456780000 - rng.gen_range(0, 2)),
+                lock_args.clone(),
+            );
+            builder = builder
+                .input(CellInput::new(previous_out_point.clone(), 0))
+                .output(output_cell)
+                .output_data(output_data.pack())
+                .witness(WitnessArgs::new_builder().build().as_bytes().pack());
+            if rng.gen_range(0, 4) != 0 {
+                builder = builder.header_dep(deposit_header.hash());
+            }
+            CellMetaBuilder::from_cell_output(cell, Bytes::from(vec![0; 8]))
+                .out_point(previous_out_point)
+                .transaction_info(TransactionInfo {
+                    block_hash: deposit_header.hash(),
+                    block_number: deposit_header.number(),
+                    block_epoch: EpochNumberWithFraction::new(35, 554, 1000),
+                    index: 0,
+                })
+                .build()
+        } else {
+            // Phase 2, the withdrawing cell is spent with the compensation.
+            let since: u64 = match rng.gen_range(0, 6) {
+                0 => 0x2003e802290002f3,
+                1 => 0xa003e8022a0002f3,
+                2 => 0x20000000000002f4,
+                3 => 0x2003e803e80002f4,
+                _ => 0x2003e8022a0002f3,
+            };
+            let deposit_header_index: u64 = match rng.gen_range(0, 6) {
+                0 => 1,
+                1 => 2,
+                _ => 0,
+            };
+            let input_type = if rng.gen_range(0, 8) == 0 {
+                Bytes::from(vec![0; 4])
+            } else {
+                Bytes::from(deposit_header_index.to_le_bytes().to_vec())
+            };
+            builder = builder
+                .input(CellInput::new(previous_out_point.clone(), since))
+                .output(cell_output_with_only_capacity(
+                    123468105678 + rng.gen_range(0, 2),
+                ))
+                .output_data(Bytes::new().pack())
+                .header_dep(deposit_header.hash())
+                .witness(
+                    WitnessArgs::new_builder()
+                        .input_type(Some(input_type).pack())
+                        .build()
+                        .as_bytes()
+                        .pack(),
+                );
+            if rng.gen_range(0, 4) != 0 {
+                builder = builder.header_dep(withdraw_header.hash());
+            }
+            let block_number = deposit_header.number() + rng.gen_range(0, 2);
+            CellMetaBuilder::from_cell_output(
+                cell,
+                Bytes::from(block_number.to_le_bytes().to_vec()),
+            )
+            .out_point(previous_out_point)
+            .transaction_info(TransactionInfo {
+                block_hash: withdraw_header.hash(),
+                block_number: withdraw_header.number(),
+                block_epoch: EpochNumberWithFraction::new(575, 610, 1100),
+                index: 0,
+            })
+            .build()
+        };
+        let mut resolved_inputs = vec![input_cell_meta];
+
+        // Sometimes also deposit a cell, paid by a normal input.
+        if rng.gen() {
+            let capacity = Capacity::shannons(100000000000);
+            let (normal_cell, normal_out_point) =
+                gen_normal_cell(&mut data_loader, capacity, lock_args.clone());
+            let (deposit_cell, _) = gen_dao_cell(&mut data_loader, capacity, lock_args.clone());
+            let deposit_data = if rng.gen_range(0, 4) == 0 {
+                Bytes::from(vec![1; 8])
+            } else {
+                Bytes::from(vec![0; 8])
+            };
+            builder = builder
+                .input(CellInput::new(normal_out_point.clone(), 0))
+                .output(deposit_cell)
+                .output_data(deposit_data.pack())
+                .witness(WitnessArgs::new_builder().build().as_bytes().pack());
+            resolved_inputs.push(
+                CellMetaBuilder::from_cell_output(normal_cell, Bytes::new())
+                    .out_point(normal_out_point)
+                    .build(),
+            );
+        }
+
+        let (tx, resolved_cell_deps) = complete_tx(&mut data_loader, builder);
+        let rtx = ResolvedTransaction {
+            transaction: sign_tx(tx, &privkey),
+            resolved_inputs,
+            resolved_cell_deps,
+            resolved_dep_groups: vec![],
+        };
+        if let Err(err) = verify_natively_and_in_vm(&data_loader, rtx, &dao_script) {
+            errors.push(err);
+        }
+    }
+    // The random transactions cover both outcomes.
+    assert!(!errors.is_empty() && errors.len() < 48);
+}
+
+#[test]
+fn test_calculate_maximum_withdraw() {
+    let mut data_loader = DummyDataLoader::new();
+    let (_, lock_args) = gen_lock();
+    let (deposit_header, _) = gen_header(1554, 10000000, 35, 1000, 1000);
+    let (withdraw_header, _) = gen_header(2000610, 10001000, 575, 2000000, 1100);
+    let (cell, _) = gen_dao_cell(
+        &mut data_loader,
+        Capacity::shannons(123456780000),
+        lock_args,
+    );
+    let data = 1554u64.to_le_bytes();
+
+    // The output of `test_dao_single_cell`, one more shannon fails.
+    assert_eq!(
+        calculate_maximum_withdraw(&deposit_header, &withdraw_header, &cell, &data),
+        Ok(123468105678)
+    );
+    assert_eq!(
+        calculate_maximum_withdraw(&deposit_header, &deposit_header, &cell, &data),
+        Ok(123456780000)
+    );
+
+    // 102 bytes are occupied.
+    let occupied_cell = cell
+        .clone()
+        .as_builder()
+        .capacity(Capacity::shannons(10200000000).pack())
+        .build();
+    assert_eq!(
+        calculate_maximum_withdraw(&deposit_header, &withdraw_header, &occupied_cell, &data),
+        Ok(10200000000)
+    );
+    let underfunded_cell = cell
+        .clone()
+        .as_builder()
+        .capacity(Capacity::shannons(10199999999).pack())
+        .build();
+    assert_eq!(
+        calculate_maximum_withdraw(&deposit_header, &withdraw_header, &underfunded_cell, &data),
+        Err(DaoError::Overflow)
+    );
+
+    let (zero_rate_header, _) = gen_header(1554, 0, 35, 1000, 1000);
+    assert_eq!(
+        calculate_maximum_withdraw(&zero_rate_header, &withdraw_header, &cell, &data),
+        Err(DaoError::Overflow)
+    );
+
+    // The compensation is truncated to 64 bits, only the final sum reports an overflow.
+    let (deposit_header, _) = gen_header(1554, 1, 35, 1000, 1000);
+    let (tripled_header, _) = gen_header(2000610, 3, 575, 2000000, 1100);
+    let (doubled_header, _) = gen_header(2000610, 2, 575, 2000000, 1100);
+    let truncated_cell = cell
+        .clone()
+        .as_builder()
+        .capacity(Capacity::shannons((1 << 63) + 10200000000).pack())
+        .build();
+    assert_eq!(
+        calculate_maximum_withdraw(&deposit_header, &tripled_header, &truncated_cell, &data),
+        Ok((1 << 63) + 10200000000)
+    );
+    let overflowing_cell = cell
+        .as_builder()
+        .capacity(Capacity::shannons((1 << 63) - 1 + 10200000000).pack())
+        .build();
+    assert_eq!(
+        calculate_maximum_withdraw(&deposit_header, &doubled_header, &overflowing_cell, &data),
+        Err(DaoError::Overflow)
+    );
+}
+
+#[test]
+fn test_minimal_withdraw_since() {
+    let (deposit_header, _) = gen_header(1554, 10000000, 35, 1000, 1000);
+    let minimal_since = |withdraw_header: &HeaderView| {
+        minimal_withdraw_since(&deposit_header, withdraw_header).map(Since::absolute_epoch)
+    };
+
+    // The since of `test_dao_single_cell` and `test_dao_single_cell_epoch_edge`.
+    let (withdraw_header, _) = gen_header(2000610, 10001000, 575, 2000000, 1100);
+    assert_eq!(
+        minimal_since(&withdraw_header),
+        Ok(Since::from_raw_value(0x2003e8022a0002f3))
+    );
+    let (withdraw_header, _) = gen_header(2000555, 10001000, 575, 2000000, 1000);
+    assert_eq!(
+        minimal_since(&withdraw_header),
+        Ok(Since::from_raw_value(0x2003e8022a0002f3))
+    );
+    // Exactly one lock period, and one block more.
+    let (withdraw_header, _) = gen_header(2000554, 10001000, 215, 2000000, 1000);
+    assert_eq!(
+        minimal_withdraw_since(&deposit_header, &withdraw_header),
+        Ok(since::EpochNumberWithFraction::new(215, 554, 1000).unwrap())
+    );
+    let (withdraw_header, _) = gen_header(2000555, 10001000, 215, 2000000, 1000);
+    assert_eq!(
+        minimal_withdraw_since(&deposit_header, &withdraw_header),
+        Ok(since::EpochNumberWithFraction::new(395, 554, 1000).unwrap())
+    );
+
+    // The since of `test_dao_single_cell_start_of_epoch` and `test_dao_single_cell_end_of_epoch`.
+    let (start_header, _) = gen_header(1000, 10000000, 35, 1000, 1000);
+    let (withdraw_header, _) = gen_header(2000001, 10001000, 575, 2000000, 1100);
+    assert_eq!(
+        minimal_withdraw_since(&start_header, &withdraw_header).map(Since::absolute_epoch),
+        Ok(Since::from_raw_value(0x2003e800000002f3))
+    );
+    let (end_header, _) = gen_header(1999, 10000000, 35, 1000, 1000);
+    let (withdraw_header, _) = gen_header(2000000, 10001000, 576, 2000000, 1100);
+    assert_eq!(
+        minimal_withdraw_since(&end_header, &withdraw_header).map(Since::absolute_epoch),
+        Ok(Since::from_raw_value(0x2003e803e70002f3))
+    );
+
+    let (same_epoch_header, _) = gen_header(1554, 10000000, 35, 1000, 1000);
+    assert_eq!(
+        minimal_since(&same_epoch_header),
+        Err(DaoError::InvalidWithdrawBlock)
+    );
+    // The genesis epoch is the only one with a zero length.
+    let zero_length_header = HeaderBuilder::default().build();
+    assert_eq!(
+        minimal_since(&zero_length_header),
+        Err(DaoError::IncorrectEpoch)
+    );
+}
+
+#[test]
+fn test_is_withdrawable() {
+    let (deposit_header, _) = gen_header(1554, 10000000, 35, 1000, 1000);
+    let (withdraw_header, _) = gen_header(2000610, 10001000, 575, 2000000, 1100);
+    let withdrawable = |number, index, length| {
+        let tip_epoch = since::EpochNumberWithFraction::new(number, index, length).unwrap();
+        is_withdrawable(&deposit_header, &withdraw_header, tip_epoch).unwrap()
+    };
+
+    assert!(!withdrawable(575, 610, 1100));
+    assert!(!withdrawable(755, 553, 1000));
+    assert!(!withdrawable(755, 1, 2));
+    assert!(withdrawable(755, 554, 1000));
+    assert!(withdrawable(755, 2, 3));
+    assert!(withdrawable(756, 0, 1000));
+}
+
+fn verify_built_tx(
+    data_loader: &mut DummyDataLoader,
+    tx: TransactionView,
+    resolved_inputs: Vec<CellMeta>,
+    privkey: &Privkey,
+    dao_script: &Script,
+) -> Result<(), DaoError> {
+    let (tx, resolved_cell_deps) = complete_tx(data_loader, tx.as_advanced_builder());
+    let rtx = ResolvedTransaction {
+        transaction: sign_tx(tx, privkey),
+        resolved_inputs,
+        resolved_cell_deps,
+        resolved_dep_groups: vec![],
+    };
+    verify_natively_and_in_vm(data_loader, rtx, dao_script)
+}
+
+#[test]
+fn test_dao_builders() {
+    let mut data_loader = DummyDataLoader::new();
+    let (privkey, lock_args) = gen_lock();
+    let (deposit_header, deposit_epoch) = gen_header(1554, 10000000, 35, 1000, 1000);
+    let (withdraw_header, withdraw_epoch) = gen_header(2000610, 10001000, 575, 2000000, 1100);
+    for (header, epoch) in [
+        (&deposit_header, deposit_epoch),
+        (&withdraw_header, withdraw_epoch),
+    ] {
+        data_loader.headers.insert(header.hash(), header.clone());
+        data_loader.epoches.insert(header.hash(), epoch);
+    }
+    let (dao_cell, _) = gen_dao_cell(
+        &mut data_loader,
+        Capacity::shannons(123456780000),
+        lock_args.clone(),
+    );
+    let dao_type = dao_cell.type_().to_opt().unwrap();
+    let empty_witness = WitnessArgs::default().as_bytes().pack();
+
+    // Deposit from a normal cell.
+    let (normal_cell, normal_out_point) = gen_normal_cell(
+        &mut data_loader,
+        Capacity::shannons(123456780000),
+        lock_args,
+    );
+    let tx = TransactionBuilder::default()
+        .input(CellInput::new(normal_out_point.clone(), 0))
+        .witness(empty_witness.clone())
+        .build();
+    let tx = deposit(&tx, dao_type.clone(), dao_cell.lock(), 123456780000);
+    assert_eq!(tx.outputs().get(0), Some(dao_cell.clone()));
+    assert_eq!(tx.outputs_data().get(0).unwrap().raw_data()[..], [0; 8]);
+    let resolved_inputs = vec![CellMetaBuilder::from_cell_output(normal_cell, Bytes::new())
+        .out_point(normal_out_point)
+        .build()];
+    assert_eq!(
+        verify_built_tx(&mut data_loader, tx, resolved_inputs, &privkey, &dao_type),
+        Ok(())
+    );
+
+    // Phase 1, the deposited cell is committed in the deposit block.
+    let deposited_out_point = generate_random_out_point();
+    let tx = TransactionBuilder::default()
+        .witness(empty_witness.clone())
+        .build();
+    let tx = withdraw_phase1(
+        &tx,
+        deposited_out_point.clone(),
+        dao_cell.clone(),
+        &deposit_header,
+    )
+    .unwrap();
+    assert_eq!(tx.outputs().get(0), Some(dao_cell.clone()));
+    assert_eq!(
+        tx.outputs_data().get(0).unwrap().raw_data()[..],
+        1554u64.to_le_bytes()
+    );
+    assert_eq!(
+        tx.header_deps().into_iter().collect::<Vec<_>>(),
+        vec![deposit_header.hash()]
+    );
+    let resolved_inputs =
+        vec![
+            CellMetaBuilder::from_cell_output(dao_cell.clone(), Bytes::from(vec![0; 8]))
+                .out_point(deposited_out_point.clone())
+                .transaction_info(TransactionInfo {
+                    block_hash: deposit_header.hash(),
+                    block_number: deposit_header.number(),
+                    block_epoch: EpochNumberWithFraction::new(35, 554, 1000),
+                    index: 0,
+                })
+                .build(),
+        ];
+    assert_eq!(
+        verify_built_tx(&mut data_loader, tx, resolved_inputs, &privkey, &dao_type),
+        Ok(())
+    );
+
+    // Phase 2, the withdrawing cell is committed in the withdraw block, whose header is
+    // already a header dep.
+    let withdrawing_out_point = generate_random_out_point();
+    let tx = TransactionBuilder::default()
+        .header_dep(withdraw_header.hash())
+        .build();
+    let tx = withdraw_phase2(
+        &tx,
+        withdrawing_out_point.clone(),
+        &deposit_header,
+        &withdraw_header,
+    )
+    .unwrap();
+    assert_eq!(
+        tx.header_deps().into_iter().collect::<Vec<_>>(),
+        vec![withdraw_header.hash(), deposit_header.hash()]
+    );
+    let since: u64 = tx.inputs().get(0).unwrap().since().unpack();
+    assert_eq!(since, 0x2003e8022a0002f3);
+    let witness = WitnessArgs::from_slice(&tx.witnesses().get(0).unwrap().raw_data()).unwrap();
+    assert_eq!(
+        witness.input_type().to_opt().unwrap().raw_data()[..],
+        1u64.to_le_bytes()
+    );
+
+    let data = 1554u64.to_le_bytes();
+    let maximum_withdraw =
+        calculate_maximum_withdraw(&deposit_header, &withdraw_header, &dao_cell, &data).unwrap();
+    for (capacity, expected) in [
+        (maximum_withdraw, Ok(())),
+        (maximum_withdraw + 1, Err(DaoError::IncorrectCapacity)),
+    ] {
+        let tx = tx
+            .as_advanced_builder()
+            .output(cell_output_with_only_capacity(capacity))
+            .output_data(Bytes::new().pack())
+            .build();
+        let resolved_inputs =
+            vec![
+                CellMetaBuilder::from_cell_output(dao_cell.clone(), Bytes::from(data.to_vec()))
+                    .out_point(withdrawing_out_point.clone())
+                    .transaction_info(TransactionInfo {
+                        block_hash: withdraw_header.hash(),
+                        block_number: withdraw_header.number(),
+                        block_epoch: EpochNumberWithFraction::new(575, 610, 1100),
+                        index: 0,
+                    })
+                    .build(),
+            ];
+        assert_eq!(
+            verify_built_tx(&mut data_loader, tx, resolved_inputs, &privkey, &dao_type),
+            expected
+        );
+    }
+
+    // The withdrawing cell would not be at the index of the deposited cell.
+    let unpaired_tx = TransactionBuilder::default()
+        .output(cell_output_with_only_capacity(123456780000))
+        .output_data(Bytes::new().pack())
+        .build();
+    assert_eq!(
+        withdraw_phase1(
+            &unpaired_tx,
+            deposited_out_point.clone(),
+            dao_cell.clone(),
+            &deposit_header
+        ),
+        Err(DaoBuildError::UnpairedInputsOutputs)
+    );
+    let missing_data_tx = TransactionBuilder::default()
+        .input(CellInput::new(generate_random_out_point(), 0))
+        .output(cell_output_with_only_capacity(123456780000))
+        .build();
+    assert_eq!(
+        withdraw_phase1(
+            &missing_data_tx,
+            deposited_out_point,
+            dao_cell,
+            &deposit_header
+        ),
+        Err(DaoBuildError::UnpairedOutputsData)
+    );
+
+    let empty_tx = TransactionBuilder::default().build();
+    assert_eq!(
+        withdraw_phase2(
+            &empty_tx,
+            withdrawing_out_point.clone(),
+            &withdraw_header,
+            &deposit_header
+        ),
+        Err(DaoBuildError::Dao(DaoError::InvalidWithdrawBlock))
+    );
+    let crowded_tx = TransactionBuilder::default()
+        .header_deps((0..256).map(|_| generate_random_out_point().tx_hash()))
+        .build();
+    assert_eq!(
+        withdraw_phase2(
+            &crowded_tx,
+            withdrawing_out_point.clone(),
+            &deposit_header,
+            &withdraw_header
+        ),
+        Err(DaoBuildError::HeaderDepIndexTooLarge(256))
+    );
+    let invalid_witness_tx = empty_tx
+        .as_advanced_builder()
+        .witness(Bytes::from(vec![42]).pack())
+        .build();
+    assert_eq!(
+        withdraw_phase2(
+            &invalid_witness_tx,
+            withdrawing_out_point,
+            &deposit_header,
+            &withdraw_header
+        ),
+        Err(DaoBuildError::InvalidWitnessArgs(0))
+    );
+}
@@ -0,0 +1,30 @@
+use crate::{
+    debug_symbols::{get_debug_symbols, write_debug_files},
+    get_binary, SystemScript,
+};
+use std::{env, fs, io};
+
+#[test]
+fn test_unknown_hash() {
+    assert!(get_debug_symbols(&[0u8; 32]).is_none());
+}
+
+#[test]
+fn test_write_debug_files() {
+    let dir = env::temp_dir().join(format!("ckb-system-scripts-debug-{}", std::process::id()));
+    for script in SystemScript::iter() {
+        match script.debug_symbols() {
+            Some(debug_symbols) => {
+                let (binary_path, debug_path) =
+                    write_debug_files(script, &dir).expect("write debug files");
+                assert_eq!(fs::read(binary_path).unwrap(), &get_binary(script)[..]);
+                assert_eq!(fs::read(debug_path).unwrap(), &debug_symbols[..]);
+            }
+            None => {
+                let err = write_debug_files(script, &dir).unwrap_err();
+                assert_eq!(err.kind(), io::ErrorKind::NotFound, "{}", script);
+            }
+        }
+    }
+    let _ = fs::remove_dir_all(dir);
+}
@@ -0,0 +1,135 @@
+use crate::{
+    deployment::{Deployment, DevChainSpec, DEV, MAINNET, TESTNET},
+    versions::find_version,
+    SystemScript,
+};
+use ckb_chain_spec::ChainSpec;
+use ckb_resource::Resource;
+use ckb_types::{
+    bytes::Bytes,
+    core::{BlockView, DepType, ScriptHashType},
+    packed::{OutPoint, OutPointVec, Script},
+    prelude::*,
+};
+use std::{env, fs};
+
+const ONE_CKB: u64 = 100_000_000;
+
+fn build_genesis(resource: Resource) -> BlockView {
+    ChainSpec::load_from(&resource)
+        .expect("load chain spec")
+        .build_genesis()
+        .expect("build genesis")
+}
+
+fn check_deployment(deployment: &Deployment, genesis: &BlockView) {
+    let cellbase = genesis.transaction(0).unwrap();
+    let dep_group_tx = genesis.transaction(1).unwrap();
+    assert_eq!(cellbase.hash(), deployment.genesis_tx_hash.pack());
+    assert_eq!(dep_group_tx.hash(), deployment.dep_group_tx_hash.pack());
+
+    for script in SystemScript::iter() {
+        let out_point = deployment.code_out_point(script);
+        let index: u32 = out_point.index().unpack();
+        let (output, data) = cellbase.output_with_data(index as usize).unwrap();
+        assert_eq!(
+            find_version(&ckb_hash::blake2b_256(&data)).map(|version| version.script()),
+            Some(script)
+        );
+        match deployment.hash_type(script) {
+            ScriptHashType::Type => {
+                let type_script = output.type_().to_opt().unwrap();
+                assert_eq!(
+                    type_script.calc_script_hash(),
+                    deployment.code_hash(script),
+                    "{}",
+                    script
+                );
+            }
+            _ => assert_eq!(
+                deployment.code_hash(script),
+                script.data_hash().pack(),
+                "{}",
+                script
+            ),
+        }
+
+        let cell_dep = deployment.cell_dep(script);
+        match deployment.dep_group_out_point(script) {
+            Some(dep_group) => {
+                assert_eq!(cell_dep.dep_type(), DepType::DepGroup.into());
+                assert_eq!(cell_dep.out_point(), dep_group);
+                let index: u32 = dep_group.index().unpack();
+                let (_, data) = dep_group_tx.output_with_data(index as usize).unwrap();
+                let out_points = OutPointVec::from_slice(&data).unwrap();
+                let expected: Vec<OutPoint> = vec![
+                    deployment.code_out_point(SystemScript::Secp256k1Data),
+                    out_point,
+                ];
+                assert_eq!(out_points.into_iter().collect::<Vec<_>>(), expected);
+            }
+            None => {
+                assert_eq!(cell_dep.dep_type(), DepType::Code.into());
+                assert_eq!(cell_dep.out_point(), out_point);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_mainnet_and_testnet() {
+    check_deployment(
+        &MAINNET,
+        &build_genesis(Resource::bundled("specs/mainnet.toml".to_string())),
+    );
+    check_deployment(
+        &TESTNET,
+        &build_genesis(Resource::bundled("specs/testnet.toml".to_string())),
+    );
+}
+
+#[test]
+fn test_build_script() {
+    let args = Bytes::from(vec![1; 20]);
+    let script = MAINNET.script(SystemScript::Sighash, args.clone());
+    assert_eq!(script.hash_type(), ScriptHashType::Type.into());
+    assert_eq!(
+        script.code_hash(),
+        SystemScript::Sighash.type_hash().unwrap().pack()
+    );
+    assert_eq!(script.args().raw_data(), args);
+    assert_eq!(
+        TESTNET.hash_type(SystemScript::Secp256k1Data),
+        ScriptHashType::Data
+    );
+}
+
+#[test]
+fn test_dev_chain_spec() {
+    let sighash_lock = |args: &str| {
+        let mut bytes = vec![0; 20];
+        faster_hex::hex_decode(args.as_bytes(), &mut bytes).unwrap();
+        DEV.script(SystemScript::Sighash, Bytes::from(bytes))
+    };
+    let mut burn_args = vec![0; 20];
+    faster_hex::hex_decode(b"62e907b15cbf27d5425399ebf6f0fb50ebb88f18", &mut burn_args).unwrap();
+    let burn_lock = Script::new_builder()
+        .args(Bytes::from(burn_args).pack())
+        .build();
+    // issued cells of the default spec of `ckb init --chain dev`
+    let spec = DevChainSpec::default()
+        .issue(8_400_000_000 * ONE_CKB, burn_lock)
+        .issue(
+            20_000_000_000 * ONE_CKB,
+            sighash_lock("c8328aabcd9b9e8e64fbc566c4385c3bdeb219d7"),
+        )
+        .issue(
+            5_198_735_037 * ONE_CKB,
+            sighash_lock("470dcdc5e44064909650113a274b3b36aecb6dc7"),
+        );
+
+    let dir = env::temp_dir().join(format!("ckb-system-scripts-dev-{}", std::process::id()));
+    let spec_path = spec.write(&dir).expect("write dev chain spec");
+    check_deployment(&DEV, &build_genesis(Resource::file_system(spec_path)));
+    fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,93 @@
+use super::{
+    blake160,
+    secp256k1_blake160_sighash_all::{gen_lock_script, gen_tx_with_grouped_args},
+    DummyDataLoader,
+};
+use crate::{
+    fee::{calculate_fee, LockWitness, WitnessEstimator},
+    multisig::MultisigConfig,
+    signer::sign_tx,
+};
+use ckb_crypto::secp::Generator;
+use ckb_types::{bytes::Bytes, packed::WitnessArgs, prelude::*};
+use rand::thread_rng;
+
+#[test]
+fn test_calculate_fee() {
+    assert_eq!(calculate_fee(0, 1000), Some(0));
+    assert_eq!(calculate_fee(500, 1000), Some(500));
+    assert_eq!(calculate_fee(1, 1), Some(1));
+    assert_eq!(calculate_fee(1001, 1), Some(2));
+
+    // The product overflows 64 bits before the division.
+    assert_eq!(calculate_fee(1000, u64::MAX), Some(u64::MAX));
+    assert_eq!(calculate_fee(1, u64::MAX), Some(u64::MAX / 1000 + 1));
+    assert_eq!(calculate_fee(1001, u64::MAX), None);
+}
+
+#[test]
+fn test_estimate_sighash_tx() {
+    let mut rng = thread_rng();
+    let mut data_loader = DummyDataLoader::new();
+    let privkey = Generator::random_privkey();
+    let privkey2 = Generator::random_privkey();
+    let pubkey_hash = blake160(&privkey.pubkey().expect("pubkey").serialize());
+    let pubkey_hash2 = blake160(&privkey2.pubkey().expect("pubkey").serialize());
+    let tx = gen_tx_with_grouped_args(
+        &mut data_loader,
+        vec![(pubkey_hash, 2), (pubkey_hash2, 1)],
+        &mut rng,
+    );
+    let tx = tx.as_advanced_builder().set_witnesses(vec![]).build();
+
+    let estimate = WitnessEstimator::new()
+        .estimate(&tx, &data_loader)
+        .expect("estimate");
+    let signed = sign_tx(&tx, &data_loader, &privkey).expect("sign");
+    let signed = sign_tx(&signed, &data_loader, &privkey2).expect("sign");
+    assert_eq!(estimate.size, signed.data().serialized_size_in_block());
+    assert_eq!(estimate.tx.witnesses().len(), 3);
+    assert_eq!(estimate.fee(1000), Some(estimate.size as u64));
+}
+
+#[test]
+fn test_estimate_described_locks() {
+    let mut rng = thread_rng();
+    let mut data_loader = DummyDataLoader::new();
+    let config = MultisigConfig::new(0, 2, vec![[1; 20], [2; 20], [3; 20]]).unwrap();
+    let tx = gen_tx_with_grouped_args(
+        &mut data_loader,
+        vec![(vec![1; 20].into(), 1), (vec![2; 20].into(), 2)],
+        &mut rng,
+    );
+    let tx = tx.as_advanced_builder().set_witnesses(vec![]).build();
+
+    let estimator = WitnessEstimator::new()
+        .lock(
+            &gen_lock_script(vec![2; 20].into()),
+            LockWitness::Multisig(config.clone()),
+        )
+        .dao_withdraw(2, 1);
+    let estimate = estimator.estimate(&tx, &data_loader).expect("estimate");
+    let witness = |index: usize| {
+        WitnessArgs::from_slice(&estimate.tx.witnesses().get(index).unwrap().raw_data())
+            .expect("witness args")
+    };
+    assert_eq!(
+        witness(0).lock().to_opt().unwrap().raw_data(),
+        Bytes::from(vec![0; 65])
+    );
+    let lock = witness(1).lock().to_opt().unwrap().raw_data();
+    assert_eq!(lock.len(), 4 + 20 * 3 + 65 * 2);
+    assert_eq!(lock.slice(..config.script_size()), config.to_bytes());
+    assert!(witness(1).input_type().is_none());
+    assert!(witness(2).lock().is_none());
+    assert_eq!(
+        witness(2).input_type().to_opt().unwrap().raw_data(),
+        Bytes::from(1u64.to_le_bytes().to_vec())
+    );
+    assert_eq!(
+        estimator.fill(&estimate.tx, &data_loader).expect("fill"),
+        estimate.tx
+    );
+}
@@ -1,7 +1,45 @@
+mod address;
 mod dao;
+#[cfg(feature = "debug-symbols")]
+mod debug_symbols;
+#[cfg(all(
+    feature = "sighash",
+    feature = "multisig",
+    feature = "dao",
+    feature = "secp256k1-data"
+))]
+mod deployment;
+#[cfg(feature = "sighash")]
+mod fee;
+mod script_error;
 mod secp256k1_blake160_multisig_all;
 mod secp256k1_blake160_sighash_all;
+#[cfg(feature = "sighash")]
+mod signer;
+mod since;
+#[cfg(all(
+    feature = "sighash",
+    feature = "multisig",
+    feature = "dao",
+    feature = "secp256k1-data"
+))]
+mod system_script;
+#[cfg(all(
+    feature = "sighash",
+    feature = "multisig",
+    feature = "dao",
+    feature = "secp256k1-data"
+))]
+mod type_id;
+#[cfg(all(
+    feature = "sighash",
+    feature = "multisig",
+    feature = "dao",
+    feature = "secp256k1-data"
+))]
+mod versions;
 
+use crate::signer::CellOutputProvider;
 use ckb_crypto::secp::Privkey;
 use ckb_traits::{CellDataProvider, HeaderProvider};
 use ckb_types::{
@@ -14,7 +52,7 @@ use ckb_types::{
 use lazy_static::lazy_static;
 use std::collections::HashMap;
 
-pub const MAX_CYCLES: u64 = std::u64::MAX;
+pub const MAX_CYCLES: u64 = u64::MAX;
 pub const SIGNATURE_SIZE: usize = 65;
 
 lazy_static! {
@@ -52,6 +90,12 @@ impl CellDataProvider for DummyDataLoader {
     }
 }
 
+impl CellOutputProvider for DummyDataLoader {
+    fn get_cell_output(&self, out_point: &OutPoint) -> Option<CellOutput> {
+        self.cells.get(out_point).map(|(output, _)| output.clone())
+    }
+}
+
 impl HeaderProvider for DummyDataLoader {
     fn get_header(&self, block_hash: &Byte32) -> Option<HeaderView> {
         self.headers.get(block_hash).cloned()
@@ -85,11 +129,7 @@ pub fn sign_tx_by_input_group(
                 blake2b.update(&tx_hash.raw_data());
                 // digest the first witness
                 let witness = WitnessArgs::new_unchecked(tx.witnesses().get(i).unwrap().unpack());
-                let zero_lock: Bytes = {
-                    let mut buf = Vec::new();
-                    buf.resize(SIGNATURE_SIZE, 0);
-                    buf.into()
-                };
+                let zero_lock: Bytes = vec![0; SIGNATURE_SIZE].into();
                 let witness_for_digest = witness
                     .clone()
                     .as_builder()
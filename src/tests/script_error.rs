@@ -0,0 +1,118 @@
+use crate::script_error::{
+    decode_script_error, DaoError, MultisigError, SighashError, SystemScriptError,
+};
+use crate::{TYPE_HASH_DAO, TYPE_HASH_SECP256K1_BLAKE160_SIGHASH_ALL};
+use ckb_error::{Error, ErrorKind};
+use ckb_script::ScriptError;
+use ckb_types::{core::ScriptHashType, packed::Script, prelude::*};
+
+fn script(code_hash: [u8; 32], hash_type: ScriptHashType) -> Script {
+    Script::new_builder()
+        .code_hash(code_hash.pack())
+        .hash_type(hash_type.into())
+        .build()
+}
+
+fn validation_failure(script: &Script, exit_code: i8) -> Error {
+    ScriptError::validation_failure(script, exit_code)
+        .input_lock_script(1)
+        .into()
+}
+
+#[test]
+fn test_exit_codes() {
+    assert_eq!(
+        SighashError::from_exit_code(-2),
+        Some(SighashError::Encoding)
+    );
+    assert_eq!(DaoError::from_exit_code(-11), Some(DaoError::Encoding));
+    assert_eq!(
+        DaoError::from_exit_code(-2),
+        Some(DaoError::WrongNumberOfArguments)
+    );
+    assert_eq!(SighashError::from_exit_code(-52), None);
+    assert_eq!(
+        MultisigError::from_exit_code(-52),
+        Some(MultisigError::Verification)
+    );
+
+    let error = MultisigError::MultisigScriptHash;
+    assert_eq!(error.exit_code(), -51);
+    assert_eq!(error.name(), "ERROR_MULTSIG_SCRIPT_HASH");
+    assert_eq!(
+        error.to_string(),
+        "multisig script hash mismatches lock args"
+    );
+    assert_eq!(
+        SighashError::LoadingData.name(),
+        "CKB_SECP256K1_HELPER_ERROR_LOADING_DATA"
+    );
+    for code in i8::MIN..=i8::MAX {
+        if let Some(error) = DaoError::from_exit_code(code) {
+            assert_eq!(error.exit_code(), code);
+        }
+    }
+}
+
+#[test]
+fn test_decode_script_error() {
+    let lock = script(
+        TYPE_HASH_SECP256K1_BLAKE160_SIGHASH_ALL,
+        ScriptHashType::Type,
+    );
+    let error = decode_script_error(&validation_failure(&lock, -31)).unwrap();
+    assert_eq!(
+        error,
+        SystemScriptError::Sighash(SighashError::PubkeyBlake160Hash)
+    );
+    assert_eq!(error.exit_code(), -31);
+    assert_eq!(
+        error.to_string(),
+        "secp256k1_blake160_sighash_all: pubkey hash mismatches lock args"
+    );
+
+    let dao = script(TYPE_HASH_DAO, ScriptHashType::Type);
+    assert_eq!(
+        decode_script_error(&validation_failure(&dao, -2)),
+        Some(SystemScriptError::Dao(DaoError::WrongNumberOfArguments))
+    );
+    // Not defined by the script, `c/dao.c` skips -18.
+    assert_eq!(decode_script_error(&validation_failure(&dao, -3)), None);
+    assert_eq!(decode_script_error(&validation_failure(&dao, -18)), None);
+
+    let unknown = script([1; 32], ScriptHashType::Type);
+    assert_eq!(
+        decode_script_error(&validation_failure(&unknown, -31)),
+        None
+    );
+    let exceeded: Error = ScriptError::ExceededMaximumCycles(1)
+        .input_lock_script(0)
+        .into();
+    assert_eq!(decode_script_error(&exceeded), None);
+    assert_eq!(
+        decode_script_error(&ErrorKind::Script.because(ScriptError::MultipleMatches)),
+        None
+    );
+}
+
+#[cfg(feature = "multisig")]
+#[test]
+fn test_decode_script_error_by_data_hash() {
+    use crate::CODE_HASH_SECP256K1_BLAKE160_MULTISIG_ALL;
+    use ckb_types::packed::Byte32;
+
+    for hash_type in &[ScriptHashType::Data, ScriptHashType::Data1] {
+        let lock = script(CODE_HASH_SECP256K1_BLAKE160_MULTISIG_ALL, *hash_type);
+        assert_eq!(
+            decode_script_error(&validation_failure(&lock, -23)),
+            Some(SystemScriptError::Multisig(
+                MultisigError::IncorrectSinceFlags
+            ))
+        );
+    }
+    let lock = Script::new_builder()
+        .code_hash(Byte32::zero())
+        .hash_type(ScriptHashType::Data1.into())
+        .build();
+    assert_eq!(decode_script_error(&validation_failure(&lock, -23)), None);
+}
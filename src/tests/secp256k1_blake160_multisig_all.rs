@@ -1,4 +1,15 @@
 use super::{blake160, DummyDataLoader, MAX_CYCLES, MULTISIG_ALL_BIN, SECP256K1_DATA_BIN};
+#[cfg(feature = "multisig")]
+use crate::{
+    fee::{LockWitness, WitnessEstimator},
+    multisig::{PartialTx, PartialTxError},
+};
+use crate::{
+    multisig::{ConfigError, MultisigConfig, MultisigLockArgs},
+    script_error::MultisigError,
+    since::Since,
+    verifier::verify_multisig_all,
+};
 use ckb_crypto::secp::{Generator, Privkey};
 use ckb_error::{assert_error_eq, Error};
 use ckb_script::{ScriptError, TransactionScriptsVerifier};
@@ -13,20 +24,11 @@ use ckb_types::{
     prelude::*,
     H256,
 };
-use rand::{thread_rng, Rng};
+use rand::{rngs::SmallRng, thread_rng, Rng, SeedableRng};
 use std::sync::Arc;
 
 const SIGNATURE_SIZE: usize = 65;
 
-const ERROR_WITNESS_SIZE: i8 = -22;
-const ERROR_INVALID_PUBKEYS_CNT: i8 = -42;
-const ERROR_INVALID_THRESHOLD: i8 = -43;
-const ERROR_INVALID_REQUIRE_FIRST_N: i8 = -44;
-const ERROR_MULTSIG_SCRIPT_HASH: i8 = -51;
-const ERROR_VERIFICATION: i8 = -52;
-const ERROR_INCORRECT_SINCE_FLAG: i8 = -23;
-const ERROR_INCORRECT_SINCE_VALUE: i8 = -24;
-
 #[test]
 fn test_multisig_script_hash() {
     let mut data_loader = DummyDataLoader::new();
@@ -41,8 +43,11 @@ fn test_multisig_script_hash() {
         let verify_result = verify(&data_loader, &tx);
         assert_error_eq!(
             verify_result.unwrap_err(),
-            ScriptError::validation_failure(&lock_script, ERROR_MULTSIG_SCRIPT_HASH)
-                .input_lock_script(0),
+            ScriptError::validation_failure(
+                &lock_script,
+                MultisigError::MultisigScriptHash.exit_code()
+            )
+            .input_lock_script(0),
         );
     }
 }
@@ -65,8 +70,11 @@ fn test_invalid_flags() {
         let verify_result = verify(&data_loader, &tx);
         assert_error_eq!(
             verify_result.unwrap_err(),
-            ScriptError::validation_failure(&lock_script, ERROR_INVALID_PUBKEYS_CNT)
-                .input_lock_script(0),
+            ScriptError::validation_failure(
+                &lock_script,
+                MultisigError::InvalidPubkeysCnt.exit_code()
+            )
+            .input_lock_script(0),
         );
     }
     {
@@ -79,8 +87,11 @@ fn test_invalid_flags() {
         let verify_result = verify(&data_loader, &tx);
         assert_error_eq!(
             verify_result.unwrap_err(),
-            ScriptError::validation_failure(&lock_script, ERROR_INVALID_THRESHOLD)
-                .input_lock_script(0),
+            ScriptError::validation_failure(
+                &lock_script,
+                MultisigError::InvalidThreshold.exit_code()
+            )
+            .input_lock_script(0),
         );
     }
     {
@@ -89,12 +100,175 @@ fn test_invalid_flags() {
         let verify_result = verify(&data_loader, &tx);
         assert_error_eq!(
             verify_result.unwrap_err(),
-            ScriptError::validation_failure(&lock_script, ERROR_INVALID_REQUIRE_FIRST_N)
-                .input_lock_script(0),
+            ScriptError::validation_failure(
+                &lock_script,
+                MultisigError::InvalidRequireFirstN.exit_code()
+            )
+            .input_lock_script(0),
         );
     }
 }
 
+#[test]
+fn test_multisig_config() {
+    let keys = generate_keys(3);
+    let multi_sign_script = gen_multi_sign_script(&keys, 2, 1);
+    let config = MultisigConfig::from_bytes(&multi_sign_script).expect("valid config");
+    assert_eq!(config.require_first_n, 1);
+    assert_eq!(config.threshold, 2);
+    assert_eq!(config.pubkey_hashes.len(), 3);
+    assert_eq!(config.to_bytes(), multi_sign_script);
+    assert_eq!(&config.hash160()[..], &blake160(&multi_sign_script)[..]);
+    assert_eq!(config.lock_size(), 4 + 20 * 3 + 65 * 2);
+    assert_eq!(
+        MultisigConfig::new(1, 2, config.pubkey_hashes.clone()),
+        Ok(config.clone())
+    );
+    assert_eq!(
+        MultisigConfig::new(0, 1, vec![]),
+        Err(ConfigError::InvalidPubkeysCnt)
+    );
+    assert_eq!(
+        MultisigConfig::from_bytes(&multi_sign_script[..30]),
+        Err(ConfigError::InvalidLength)
+    );
+}
+
+#[test]
+fn test_multisig_config_errors_match_lock() {
+    let mut data_loader = DummyDataLoader::new();
+    let keys = generate_keys(3);
+    let multi_sign_script = gen_multi_sign_script(&keys, 2, 0);
+    let args = blake160(&multi_sign_script);
+    let lock_script = gen_multi_sign_lock_script(args.clone());
+    let raw_tx = gen_tx(&mut data_loader, args);
+
+    let mut reserved = multi_sign_script.to_vec();
+    reserved[0] = 1;
+    let invalid_scripts: Vec<(Bytes, ConfigError)> = vec![
+        (reserved.into(), ConfigError::InvalidReserveField),
+        (
+            gen_multi_sign_script(&[], 2, 0),
+            ConfigError::InvalidPubkeysCnt,
+        ),
+        (
+            gen_multi_sign_script(&keys, 0, 0),
+            ConfigError::InvalidThreshold,
+        ),
+        (
+            gen_multi_sign_script(&keys, 4, 0),
+            ConfigError::InvalidThreshold,
+        ),
+        (
+            gen_multi_sign_script(&keys, 2, 3),
+            ConfigError::InvalidRequireFirstN,
+        ),
+    ];
+    for (script, expected) in invalid_scripts {
+        let err = MultisigConfig::from_bytes(&script).unwrap_err();
+        assert_eq!(err, expected);
+        let tx = multi_sign_tx(raw_tx.clone(), &script, &[&keys[0], &keys[1]]);
+        let verify_result = verify(&data_loader, &tx);
+        assert_error_eq!(
+            verify_result.unwrap_err(),
+            ScriptError::validation_failure(&lock_script, err.exit_code()).input_lock_script(0),
+        );
+    }
+}
+
+#[cfg(feature = "multisig")]
+#[test]
+fn test_partial_tx_merge_and_finalize() {
+    let mut data_loader = DummyDataLoader::new();
+    let keys = generate_keys(3);
+    let multi_sign_script = gen_multi_sign_script(&keys, 2, 1);
+    let config = MultisigConfig::from_bytes(&multi_sign_script).unwrap();
+    let args = blake160(&multi_sign_script);
+    let raw_tx = gen_tx_with_extra_inputs(&mut data_loader, args, 2);
+
+    // each co-signer prepares the same transaction on its own
+    let mut partial0 =
+        PartialTx::new(&raw_tx, &data_loader, std::slice::from_ref(&config)).unwrap();
+    let mut partial2 =
+        PartialTx::new(&raw_tx, &data_loader, std::slice::from_ref(&config)).unwrap();
+    assert_eq!(partial0, partial2);
+    assert_eq!(partial0.groups().len(), 1);
+    assert_eq!(partial0.groups()[0].input_indices, vec![0, 1, 2]);
+
+    assert_eq!(partial2.sign(&keys[2]), Ok(1));
+    assert_eq!(
+        partial2.finalize(),
+        Err(PartialTxError::MissingRequiredSignature(
+            0,
+            config.pubkey_hashes[0]
+        ))
+    );
+    assert_eq!(partial0.sign(&keys[0]), Ok(1));
+    assert_eq!(
+        partial0.finalize(),
+        Err(PartialTxError::NotEnoughSignatures(0))
+    );
+    assert_eq!(partial0.sign(&generate_keys(1)[0]), Ok(0));
+    assert_eq!(
+        partial0.sign(&Privkey::from(H256::default())),
+        Err(PartialTxError::InvalidKey)
+    );
+
+    let received = PartialTx::from_bytes(&partial2.to_bytes()).unwrap();
+    assert_eq!(received, partial2);
+    partial0.merge(&received).unwrap();
+    let tx = partial0.finalize().unwrap();
+    let lock: Bytes = WitnessArgs::new_unchecked(tx.witnesses().get(0).unwrap().unpack())
+        .lock()
+        .to_opt()
+        .unwrap()
+        .unpack();
+    assert_eq!(lock.len(), config.lock_size());
+    assert_eq!(lock.slice(..config.script_size()), multi_sign_script);
+    verify(&data_loader, &tx).expect("pass verification");
+    let estimate = WitnessEstimator::new()
+        .lock(
+            &gen_multi_sign_lock_script(blake160(&multi_sign_script)),
+            LockWitness::Multisig(config.clone()),
+        )
+        .estimate(&raw_tx, &data_loader)
+        .unwrap();
+    assert_eq!(estimate.size, tx.data().serialized_size_in_block());
+
+    // signatures from elsewhere are checked against the config
+    let message = H256::from(partial2.message(0).unwrap());
+    let signature = keys[1].sign_recoverable(&message).unwrap().serialize();
+    let mut partial1 =
+        PartialTx::new(&raw_tx, &data_loader, std::slice::from_ref(&config)).unwrap();
+    assert_eq!(
+        partial1.add_signature(0, &signature),
+        Ok(config.pubkey_hashes[1])
+    );
+    let outsider = generate_keys(1).remove(0);
+    let signature = outsider.sign_recoverable(&message).unwrap().serialize();
+    let mut outsider_hash = [0u8; 20];
+    outsider_hash.copy_from_slice(&blake160(&outsider.pubkey().unwrap().serialize()));
+    assert_eq!(
+        partial1.add_signature(0, &signature),
+        Err(PartialTxError::UnknownSigner(outsider_hash))
+    );
+    assert_eq!(
+        partial1.add_signature(1, &signature),
+        Err(PartialTxError::GroupNotFound(1))
+    );
+    partial1.merge(&partial0).unwrap();
+    let tx = partial1.finalize().unwrap();
+    verify(&data_loader, &tx).expect("pass verification");
+
+    let other_tx = gen_tx_with_extra_inputs(&mut data_loader, blake160(&multi_sign_script), 2);
+    let other = PartialTx::new(&other_tx, &data_loader, &[config]).unwrap();
+    assert_eq!(partial0.merge(&other), Err(PartialTxError::TxMismatch));
+    assert_eq!(
+        PartialTx::from_bytes(&multi_sign_script),
+        Err(PartialTxError::InvalidFormat)
+    );
+}
+
 #[test]
 fn test_multisig_0_2_3_unlock() {
     let mut data_loader = DummyDataLoader::new();
@@ -125,7 +299,8 @@ fn test_multisig_0_2_3_unlock() {
         let verify_result = verify(&data_loader, &tx);
         assert_error_eq!(
             verify_result.unwrap_err(),
-            ScriptError::validation_failure(&lock_script, ERROR_WITNESS_SIZE).input_lock_script(0),
+            ScriptError::validation_failure(&lock_script, MultisigError::WitnessSize.exit_code())
+                .input_lock_script(0),
         );
     }
     {
@@ -137,7 +312,8 @@ fn test_multisig_0_2_3_unlock() {
         let verify_result = verify(&data_loader, &tx);
         assert_error_eq!(
             verify_result.unwrap_err(),
-            ScriptError::validation_failure(&lock_script, ERROR_WITNESS_SIZE).input_lock_script(0),
+            ScriptError::validation_failure(&lock_script, MultisigError::WitnessSize.exit_code())
+                .input_lock_script(0),
         );
     }
 
@@ -151,7 +327,8 @@ fn test_multisig_0_2_3_unlock() {
         let verify_result = verify(&data_loader, &tx);
         assert_error_eq!(
             verify_result.unwrap_err(),
-            ScriptError::validation_failure(&lock_script, ERROR_VERIFICATION).input_lock_script(0),
+            ScriptError::validation_failure(&lock_script, MultisigError::Verification.exit_code())
+                .input_lock_script(0),
         );
     }
     {
@@ -159,7 +336,8 @@ fn test_multisig_0_2_3_unlock() {
         let verify_result = verify(&data_loader, &tx);
         assert_error_eq!(
             verify_result.unwrap_err(),
-            ScriptError::validation_failure(&lock_script, ERROR_VERIFICATION).input_lock_script(0),
+            ScriptError::validation_failure(&lock_script, MultisigError::Verification.exit_code())
+                .input_lock_script(0),
         );
     }
 }
@@ -189,7 +367,8 @@ fn test_multisig_1_2_3_unlock() {
         let verify_result = verify(&data_loader, &tx);
         assert_error_eq!(
             verify_result.unwrap_err(),
-            ScriptError::validation_failure(&lock_script, ERROR_VERIFICATION).input_lock_script(0),
+            ScriptError::validation_failure(&lock_script, MultisigError::Verification.exit_code())
+                .input_lock_script(0),
         );
     }
 }
@@ -228,7 +407,8 @@ fn test_multisig_1_2_3_with_extra_witness_unlock() {
         let verify_result = verify(&data_loader, &tx);
         assert_error_eq!(
             verify_result.unwrap_err(),
-            ScriptError::validation_failure(&lock_script, ERROR_VERIFICATION).input_lock_script(0),
+            ScriptError::validation_failure(&lock_script, MultisigError::Verification.exit_code())
+                .input_lock_script(0),
         );
     }
 }
@@ -258,7 +438,8 @@ fn test_multisig_1_2_3_with_multiple_inputs_unlock() {
         let verify_result = verify(&data_loader, &tx);
         assert_error_eq!(
             verify_result.unwrap_err(),
-            ScriptError::validation_failure(&lock_script, ERROR_VERIFICATION).input_lock_script(0),
+            ScriptError::validation_failure(&lock_script, MultisigError::Verification.exit_code())
+                .input_lock_script(0),
         );
     }
 }
@@ -281,7 +462,8 @@ fn test_multisig_0_1_1_unlock() {
         let verify_result = verify(&data_loader, &tx);
         assert_error_eq!(
             verify_result.unwrap_err(),
-            ScriptError::validation_failure(&lock_script, ERROR_VERIFICATION).input_lock_script(0),
+            ScriptError::validation_failure(&lock_script, MultisigError::Verification.exit_code())
+                .input_lock_script(0),
         );
     }
 }
@@ -304,7 +486,8 @@ fn test_multisig_0_2_2_unlock() {
         let verify_result = verify(&data_loader, &tx);
         assert_error_eq!(
             verify_result.unwrap_err(),
-            ScriptError::validation_failure(&lock_script, ERROR_VERIFICATION).input_lock_script(0),
+            ScriptError::validation_failure(&lock_script, MultisigError::Verification.exit_code())
+                .input_lock_script(0),
         );
     }
 }
@@ -389,8 +572,11 @@ fn test_multisig_0_2_3_unlock_with_since() {
         let verify_result = verify(&data_loader, &tx);
         assert_error_eq!(
             verify_result.unwrap_err(),
-            ScriptError::validation_failure(&lock_script, ERROR_INCORRECT_SINCE_VALUE)
-                .input_lock_script(0),
+            ScriptError::validation_failure(
+                &lock_script,
+                MultisigError::IncorrectSinceValue.exit_code()
+            )
+            .input_lock_script(0),
         );
     }
     {
@@ -404,8 +590,11 @@ fn test_multisig_0_2_3_unlock_with_since() {
         let verify_result = verify(&data_loader, &tx);
         assert_error_eq!(
             verify_result.unwrap_err(),
-            ScriptError::validation_failure(&lock_script, ERROR_INCORRECT_SINCE_VALUE)
-                .input_lock_script(0),
+            ScriptError::validation_failure(
+                &lock_script,
+                MultisigError::IncorrectSinceValue.exit_code()
+            )
+            .input_lock_script(0),
         );
     }
     {
@@ -419,8 +608,11 @@ fn test_multisig_0_2_3_unlock_with_since() {
         let verify_result = verify(&data_loader, &tx);
         assert_error_eq!(
             verify_result.unwrap_err(),
-            ScriptError::validation_failure(&lock_script, ERROR_INCORRECT_SINCE_VALUE)
-                .input_lock_script(0),
+            ScriptError::validation_failure(
+                &lock_script,
+                MultisigError::IncorrectSinceValue.exit_code()
+            )
+            .input_lock_script(0),
         );
     }
     {
@@ -439,8 +631,11 @@ fn test_multisig_0_2_3_unlock_with_since() {
         let verify_result = verify(&data_loader, &tx);
         assert_error_eq!(
             verify_result.unwrap_err(),
-            ScriptError::validation_failure(&lock_script, ERROR_INCORRECT_SINCE_FLAG)
-                .input_lock_script(0),
+            ScriptError::validation_failure(
+                &lock_script,
+                MultisigError::IncorrectSinceFlags.exit_code()
+            )
+            .input_lock_script(0),
         );
     }
     {
@@ -465,6 +660,130 @@ fn test_multisig_0_2_3_unlock_with_since() {
     }
 }
 
+#[test]
+fn test_multisig_lock_args_set_group_since() {
+    let mut data_loader = DummyDataLoader::new();
+    let keys = generate_keys(3);
+    let multi_sign_script = gen_multi_sign_script(&keys, 2, 0);
+    let mut hash160 = [0u8; 20];
+    hash160.copy_from_slice(&blake160(&multi_sign_script));
+
+    let args = MultisigLockArgs {
+        hash160,
+        since: None,
+    };
+    assert_eq!(args.to_bytes(), blake160(&multi_sign_script));
+    assert_eq!(MultisigLockArgs::from_bytes(&args.to_bytes()), Some(args));
+    assert_eq!(MultisigLockArgs::from_bytes(&hash160[..19]), None);
+
+    let since_epoch = EpochNumberWithFraction::new(200, 5, 100);
+    let since = 0x2000_0000_0000_0000u64 + since_epoch.full_value();
+    let args = MultisigLockArgs {
+        hash160,
+        since: Some(Since::from_raw_value(since)),
+    };
+    let mut args_bytes = hash160.to_vec();
+    args_bytes.extend(since.to_le_bytes().iter());
+    assert_eq!(args.to_bytes(), Bytes::from(args_bytes));
+    assert_eq!(MultisigLockArgs::from_bytes(&args.to_bytes()), Some(args));
+
+    let raw_tx = gen_tx_with_extra_inputs(&mut data_loader, args.to_bytes(), 2);
+    // a later fraction of the same epoch already satisfies the constraint
+    let later = 0x2000_0000_0000_0000u64 + EpochNumberWithFraction::new(200, 3, 50).full_value();
+    let inputs: Vec<CellInput> = raw_tx
+        .inputs()
+        .into_iter()
+        .enumerate()
+        .map(|(i, input)| match i {
+            1 => input.as_builder().since(later.pack()).build(),
+            2 => input
+                .as_builder()
+                .since(since_epoch.full_value().pack())
+                .build(),
+            _ => input,
+        })
+        .collect();
+    let raw_tx = raw_tx.as_advanced_builder().set_inputs(inputs).build();
+    {
+        let tx = multi_sign_tx(raw_tx.clone(), &multi_sign_script, &[&keys[0], &keys[1]]);
+        let verify_result = verify(&data_loader, &tx);
+        assert_error_eq!(
+            verify_result.unwrap_err(),
+            ScriptError::validation_failure(
+                &gen_multi_sign_lock_script(args.to_bytes()),
+                MultisigError::IncorrectSinceFlags.exit_code()
+            )
+            .input_lock_script(0),
+        );
+    }
+
+    let raw_tx = args.set_group_since(&raw_tx, &[0, 1, 2]);
+    let sinces: Vec<u64> = raw_tx
+        .inputs()
+        .into_iter()
+        .map(|input| input.since().unpack())
+        .collect();
+    assert_eq!(sinces, vec![since, later, since]);
+    let tx = multi_sign_tx(raw_tx, &multi_sign_script, &[&keys[0], &keys[1]]);
+    verify(&data_loader, &tx).expect("pass verification");
+}
+
+#[test]
+fn test_since_satisfies_matches_lock() {
+    let mut data_loader = DummyDataLoader::new();
+    let keys = generate_keys(3);
+    let multi_sign_script = gen_multi_sign_script(&keys, 2, 0);
+    let epoch = |number, index, length| {
+        crate::since::EpochNumberWithFraction::new(number, index, length).unwrap()
+    };
+    let constraints = vec![
+        Since::absolute_epoch(epoch(200, 5, 100)),
+        Since::relative_epoch(epoch(200, 5, 100)),
+        Since::absolute_block_number(1000).unwrap(),
+    ];
+    let input_sinces = vec![
+        Since::absolute_epoch(epoch(200, 5, 100)),
+        Since::absolute_epoch(epoch(200, 1, 20)),
+        Since::absolute_epoch(epoch(200, 1, 21)),
+        Since::absolute_epoch(epoch(201, 0, 1)),
+        Since::relative_epoch(epoch(200, 1, 21)),
+        Since::relative_epoch(epoch(200, 6, 100)),
+        Since::absolute_block_number(999).unwrap(),
+        Since::absolute_block_number(1000).unwrap(),
+        Since::absolute_timestamp(1000).unwrap(),
+    ];
+    for constraint in constraints {
+        let mut hash160 = [0u8; 20];
+        hash160.copy_from_slice(&blake160(&multi_sign_script));
+        let args = MultisigLockArgs {
+            hash160,
+            since: Some(constraint),
+        };
+        let raw_tx = gen_tx(&mut data_loader, args.to_bytes());
+        for input_since in &input_sinces {
+            let inputs: Vec<CellInput> = raw_tx
+                .inputs()
+                .into_iter()
+                .map(|input| {
+                    input
+                        .as_builder()
+                        .since(input_since.as_u64().pack())
+                        .build()
+                })
+                .collect();
+            let raw_tx = raw_tx.as_advanced_builder().set_inputs(inputs).build();
+            let tx = multi_sign_tx(raw_tx, &multi_sign_script, &[&keys[0], &keys[1]]);
+            assert_eq!(
+                verify(&data_loader, &tx).is_ok(),
+                input_since.satisfies(constraint),
+                "{:?} {:?}",
+                input_since,
+                constraint
+            );
+        }
+    }
+}
+
 #[test]
 fn test_multisig_0_2_3_unlock_with_since_epoch() {
     let mut data_loader = DummyDataLoader::new();
@@ -484,8 +803,11 @@ fn test_multisig_0_2_3_unlock_with_since_epoch() {
         let verify_result = verify(&data_loader, &tx);
         assert_error_eq!(
             verify_result.unwrap_err(),
-            ScriptError::validation_failure(&lock_script, ERROR_INCORRECT_SINCE_FLAG)
-                .input_lock_script(0),
+            ScriptError::validation_failure(
+                &lock_script,
+                MultisigError::IncorrectSinceFlags.exit_code()
+            )
+            .input_lock_script(0),
         );
     }
     {
@@ -504,8 +826,11 @@ fn test_multisig_0_2_3_unlock_with_since_epoch() {
         let verify_result = verify(&data_loader, &tx);
         assert_error_eq!(
             verify_result.unwrap_err(),
-            ScriptError::validation_failure(&lock_script, ERROR_INCORRECT_SINCE_VALUE)
-                .input_lock_script(0),
+            ScriptError::validation_failure(
+                &lock_script,
+                MultisigError::IncorrectSinceValue.exit_code()
+            )
+            .input_lock_script(0),
         );
     }
     {
@@ -519,8 +844,11 @@ fn test_multisig_0_2_3_unlock_with_since_epoch() {
         let verify_result = verify(&data_loader, &tx);
         assert_error_eq!(
             verify_result.unwrap_err(),
-            ScriptError::validation_failure(&lock_script, ERROR_INCORRECT_SINCE_FLAG)
-                .input_lock_script(0),
+            ScriptError::validation_failure(
+                &lock_script,
+                MultisigError::IncorrectSinceFlags.exit_code()
+            )
+            .input_lock_script(0),
         );
     }
     {
@@ -539,8 +867,11 @@ fn test_multisig_0_2_3_unlock_with_since_epoch() {
         let verify_result = verify(&data_loader, &tx);
         assert_error_eq!(
             verify_result.unwrap_err(),
-            ScriptError::validation_failure(&lock_script, ERROR_INCORRECT_SINCE_VALUE)
-                .input_lock_script(0),
+            ScriptError::validation_failure(
+                &lock_script,
+                MultisigError::IncorrectSinceValue.exit_code()
+            )
+            .input_lock_script(0),
         );
     }
     {
@@ -656,8 +987,11 @@ fn test_genesis_time_locked_cell() {
         let verify_result = verify(&data_loader, &tx);
         assert_error_eq!(
             verify_result.unwrap_err(),
-            ScriptError::validation_failure(&lock_script, ERROR_INCORRECT_SINCE_VALUE)
-                .input_lock_script(0),
+            ScriptError::validation_failure(
+                &lock_script,
+                MultisigError::IncorrectSinceValue.exit_code()
+            )
+            .input_lock_script(0),
         );
     }
     {
@@ -858,3 +1192,118 @@ fn verify(data_loader: &DummyDataLoader, tx: &TransactionView) -> Result<u64, Er
     let resolved_tx = Arc::new(build_resolved_tx(data_loader, tx));
     TransactionScriptsVerifier::new(resolved_tx, data_loader.clone()).verify(MAX_CYCLES)
 }
+
+fn verify_natively_and_in_vm(
+    data_loader: &DummyDataLoader,
+    tx: &TransactionView,
+    lock_script: &Script,
+) -> Result<(), MultisigError> {
+    let resolved_tx = build_resolved_tx(data_loader, tx);
+    let native_result = verify_multisig_all(&resolved_tx, lock_script);
+    match native_result {
+        Ok(()) => {
+            verify(data_loader, tx).expect("pass verification");
+        }
+        Err(err) => assert_error_eq!(
+            verify(data_loader, tx).unwrap_err(),
+            ScriptError::validation_failure(lock_script, err.exit_code()).input_lock_script(0),
+        ),
+    }
+    native_result
+}
+
+fn mutate_first_lock<F: FnOnce(&mut Vec<u8>)>(tx: &TransactionView, mutate: F) -> TransactionView {
+    let witness = WitnessArgs::new_unchecked(tx.witnesses().get(0).unwrap().unpack());
+    let mut lock = witness.lock().to_opt().unwrap().raw_data().to_vec();
+    mutate(&mut lock);
+    let mut witnesses: Vec<packed::Bytes> = tx.witnesses().into_iter().collect();
+    witnesses[0] = witness
+        .as_builder()
+        .lock(Some(Bytes::from(lock)).pack())
+        .build()
+        .as_bytes()
+        .pack();
+    tx.as_advanced_builder().set_witnesses(witnesses).build()
+}
+
+#[test]
+fn test_verify_multisig_all_matches_vm() {
+    let mut rng = SmallRng::seed_from_u64(21);
+    let mut data_loader = DummyDataLoader::new();
+    let mut errors = Vec::new();
+    for _ in 0..48 {
+        let keys = generate_keys(rng.gen_range(1, 5));
+        let threshold = rng.gen_range(1, keys.len() + 1);
+        let require_first_n = rng.gen_range(0, threshold + 1);
+        let multi_sign_script =
+            gen_multi_sign_script(&keys, threshold as u8, require_first_n as u8);
+        let since = if rng.gen() {
+            Some(Since::absolute_block_number(rng.gen_range(0, 4)).unwrap())
+        } else {
+            None
+        };
+        let args = MultisigLockArgs {
+            hash160: MultisigConfig::from_bytes(&multi_sign_script)
+                .unwrap()
+                .hash160(),
+            since,
+        };
+        let lock_script = gen_multi_sign_lock_script(args.to_bytes());
+        let tx = gen_tx_with_extra_inputs(&mut data_loader, args.to_bytes(), rng.gen_range(0, 3));
+        let inputs: Vec<CellInput> = tx
+            .inputs()
+            .into_iter()
+            .map(|input| {
+                let since = match rng.gen_range(0, 4) {
+                    0 => Since::relative_block_number(4).unwrap(),
+                    1 => Since::absolute_block_number(rng.gen_range(0, 4)).unwrap(),
+                    _ => args.since_constraint(),
+                };
+                input.as_builder().since(since.as_u64().pack()).build()
+            })
+            .collect();
+        let mut tx = tx.as_advanced_builder().set_inputs(inputs).build();
+        if rng.gen() {
+            tx = tx
+                .as_advanced_builder()
+                .witness(Bytes::from(vec![rng.gen()]).pack())
+                .build();
+        }
+
+        // Mostly distinct signers, sometimes a key signs twice.
+        let signers: Vec<&Privkey> = (0..threshold)
+            .map(|i| {
+                if rng.gen_range(0, 8) == 0 {
+                    &keys[rng.gen_range(0, keys.len())]
+                } else {
+                    &keys[(i + rng.gen_range(0, 2)) % keys.len()]
+                }
+            })
+            .collect();
+        let mut tx = multi_sign_tx(tx, &multi_sign_script, &signers);
+        let signatures_offset = multi_sign_script.len();
+        tx = match rng.gen_range(0, 8) {
+            0 => mutate_first_lock(&tx, |lock| {
+                let index = rng.gen_range(signatures_offset, lock.len());
+                lock[index] ^= 1;
+            }),
+            1 => mutate_first_lock(&tx, |lock| {
+                lock.pop();
+            }),
+            2 => mutate_first_lock(&tx, |lock| lock[rng.gen_range(0, 4)] = rng.gen_range(0, 6)),
+            3 => mutate_first_lock(&tx, |lock| lock[signatures_offset + 64] = 4),
+            4 => tx
+                .as_advanced_builder()
+                .witness(Bytes::new().pack())
+                .build(),
+            _ => tx,
+        };
+        if let Err(err) = verify_natively_and_in_vm(&data_loader, &tx, &lock_script) {
+            errors.push(err);
+        }
+    }
+    // The transactions should reach various checks of the lock.
+    errors.sort_by_key(|err| err.exit_code());
+    errors.dedup();
+    assert!(errors.len() >= 5, "{:?}", errors);
+}
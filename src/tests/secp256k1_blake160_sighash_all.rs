@@ -2,6 +2,11 @@ use super::{
     blake160, sign_tx, sign_tx_by_input_group, DummyDataLoader, MAX_CYCLES, SECP256K1_DATA_BIN,
     SIGHASH_ALL_BIN,
 };
+use crate::{
+    script_error::SighashError,
+    sighash::{sighash_all_message, MessageError},
+    verifier::verify_sighash_all,
+};
 use ckb_crypto::secp::{Generator, Privkey};
 use ckb_error::assert_error_eq;
 use ckb_script::{ScriptError, TransactionScriptsVerifier};
@@ -11,18 +16,16 @@ use ckb_types::{
         cell::{CellMetaBuilder, ResolvedTransaction},
         Capacity, DepType, ScriptHashType, TransactionBuilder, TransactionView,
     },
-    packed::{CellDep, CellInput, CellOutput, OutPoint, Script, WitnessArgs, WitnessArgsBuilder},
+    packed::{
+        self, CellDep, CellInput, CellOutput, OutPoint, Script, WitnessArgs, WitnessArgsBuilder,
+    },
     prelude::*,
     H256,
 };
 use rand::{thread_rng, Rng, SeedableRng};
 use std::sync::Arc;
 
-const ERROR_ENCODING: i8 = -2;
-const ERROR_WITNESS_SIZE: i8 = -22;
-const ERROR_PUBKEY_BLAKE160_HASH: i8 = -31;
-
-fn gen_lock_script(lock_args: Bytes) -> Script {
+pub fn gen_lock_script(lock_args: Bytes) -> Script {
     let sighash_all_cell_data_hash = CellOutput::calc_data_hash(&SIGHASH_ALL_BIN);
     Script::new_builder()
         .args(lock_args.pack())
@@ -36,7 +39,7 @@ fn gen_tx(dummy: &mut DummyDataLoader, lock_args: Bytes) -> TransactionView {
     gen_tx_with_grouped_args(dummy, vec![(lock_args, 1)], &mut rng)
 }
 
-fn gen_tx_with_grouped_args<R: Rng>(
+pub fn gen_tx_with_grouped_args<R: Rng>(
     dummy: &mut DummyDataLoader,
     grouped_args: Vec<(Bytes, usize)>,
     rng: &mut R,
@@ -152,7 +155,10 @@ fn sign_tx_hash(tx: TransactionView, key: &Privkey, tx_hash: &[u8]) -> Transacti
         .build()
 }
 
-fn build_resolved_tx(data_loader: &DummyDataLoader, tx: &TransactionView) -> ResolvedTransaction {
+pub fn build_resolved_tx(
+    data_loader: &DummyDataLoader,
+    tx: &TransactionView,
+) -> ResolvedTransaction {
     let resolved_cell_deps = tx
         .cell_deps()
         .into_iter()
@@ -243,8 +249,11 @@ fn test_sighash_all_with_extra_witness_unlock() {
             TransactionScriptsVerifier::new(resolved_tx, data_loader).verify(MAX_CYCLES);
         assert_error_eq!(
             verify_result.unwrap_err(),
-            ScriptError::validation_failure(&lock_script, ERROR_PUBKEY_BLAKE160_HASH)
-                .input_lock_script(0),
+            ScriptError::validation_failure(
+                &lock_script,
+                SighashError::PubkeyBlake160Hash.exit_code()
+            )
+            .input_lock_script(0),
         );
     }
 }
@@ -289,8 +298,11 @@ fn test_sighash_all_with_grouped_inputs_unlock() {
             TransactionScriptsVerifier::new(resolved_tx, data_loader.clone()).verify(MAX_CYCLES);
         assert_error_eq!(
             verify_result.unwrap_err(),
-            ScriptError::validation_failure(&lock_script, ERROR_PUBKEY_BLAKE160_HASH)
-                .input_lock_script(0),
+            ScriptError::validation_failure(
+                &lock_script,
+                SighashError::PubkeyBlake160Hash.exit_code()
+            )
+            .input_lock_script(0),
         );
     }
 }
@@ -338,7 +350,7 @@ fn test_signing_with_wrong_key() {
         TransactionScriptsVerifier::new(resolved_tx, data_loader).verify(MAX_CYCLES);
     assert_error_eq!(
         verify_result.unwrap_err(),
-        ScriptError::validation_failure(&lock_script, ERROR_PUBKEY_BLAKE160_HASH)
+        ScriptError::validation_failure(&lock_script, SighashError::PubkeyBlake160Hash.exit_code())
             .input_lock_script(0),
     );
 }
@@ -362,7 +374,7 @@ fn test_signing_wrong_tx_hash() {
         TransactionScriptsVerifier::new(resolved_tx, data_loader).verify(MAX_CYCLES);
     assert_error_eq!(
         verify_result.unwrap_err(),
-        ScriptError::validation_failure(&lock_script, ERROR_PUBKEY_BLAKE160_HASH)
+        ScriptError::validation_failure(&lock_script, SighashError::PubkeyBlake160Hash.exit_code())
             .input_lock_script(0),
     );
 }
@@ -402,7 +414,8 @@ fn test_super_long_witness() {
         TransactionScriptsVerifier::new(resolved_tx, data_loader).verify(MAX_CYCLES);
     assert_error_eq!(
         verify_result.unwrap_err(),
-        ScriptError::validation_failure(&lock_script, ERROR_WITNESS_SIZE).input_lock_script(0),
+        ScriptError::validation_failure(&lock_script, SighashError::WitnessSize.exit_code())
+            .input_lock_script(0),
     );
 }
 
@@ -410,7 +423,7 @@ fn test_super_long_witness() {
 fn test_sighash_all_2_in_2_out_cycles() {
     // Notice this is changed due to the fact that the old tests uses
     // a different definition of WitnessArgs, hence triggering the differences.
-    const CONSUME_CYCLES: u64 = 3033463;
+    const CONSUME_CYCLES: u64 = 3408141;
 
     let mut data_loader = DummyDataLoader::new();
     let mut generator = Generator::non_crypto_safe_prng(42);
@@ -455,8 +468,7 @@ fn test_sighash_all_witness_append_junk_data() {
     let tx = sign_tx_by_input_group(tx, &privkey, 0, 2);
     let mut witnesses: Vec<_> = Unpack::<Vec<_>>::unpack(&tx.witnesses());
     // append junk data to first witness
-    let mut witness = Vec::new();
-    witness.resize(witnesses[0].len(), 0);
+    let mut witness = vec![0; witnesses[0].len()];
     witness.copy_from_slice(&witnesses[0]);
     witness.push(0);
     witnesses[0] = witness.into();
@@ -471,7 +483,8 @@ fn test_sighash_all_witness_append_junk_data() {
         TransactionScriptsVerifier::new(resolved_tx, data_loader).verify(MAX_CYCLES);
     assert_error_eq!(
         verify_result.unwrap_err(),
-        ScriptError::validation_failure(&lock_script, ERROR_ENCODING).input_lock_script(0),
+        ScriptError::validation_failure(&lock_script, SighashError::Encoding.exit_code())
+            .input_lock_script(0),
     );
 }
 
@@ -517,7 +530,7 @@ fn test_sighash_all_witness_args_ambiguity() {
         TransactionScriptsVerifier::new(resolved_tx, data_loader).verify(MAX_CYCLES);
     assert_error_eq!(
         verify_result.unwrap_err(),
-        ScriptError::validation_failure(&lock_script, ERROR_PUBKEY_BLAKE160_HASH)
+        ScriptError::validation_failure(&lock_script, SighashError::PubkeyBlake160Hash.exit_code())
             .input_lock_script(0),
     );
 }
@@ -565,7 +578,7 @@ fn test_sighash_all_witnesses_ambiguity() {
         TransactionScriptsVerifier::new(resolved_tx, data_loader).verify(MAX_CYCLES);
     assert_error_eq!(
         verify_result.unwrap_err(),
-        ScriptError::validation_failure(&lock_script, ERROR_PUBKEY_BLAKE160_HASH)
+        ScriptError::validation_failure(&lock_script, SighashError::PubkeyBlake160Hash.exit_code())
             .input_lock_script(0),
     );
 }
@@ -607,7 +620,209 @@ fn test_sighash_all_cover_extra_witnesses() {
     let verify_result = TransactionScriptsVerifier::new(resolved_tx, data_loader).verify(60000000);
     assert_error_eq!(
         verify_result.unwrap_err(),
-        ScriptError::validation_failure(&lock_script, ERROR_PUBKEY_BLAKE160_HASH)
+        ScriptError::validation_failure(&lock_script, SighashError::PubkeyBlake160Hash.exit_code())
             .input_lock_script(0),
     );
 }
+
+fn sign_group(tx: TransactionView, key: &Privkey, group_indices: &[usize]) -> TransactionView {
+    let message = sighash_all_message(&tx, group_indices).expect("signing message");
+    let sig = key.sign_recoverable(&H256::from(message)).expect("sign");
+    let mut witnesses = Unpack::<Vec<_>>::unpack(&tx.witnesses());
+    let witness = WitnessArgs::new_unchecked(witnesses[group_indices[0]].clone())
+        .as_builder()
+        .lock(Some(Bytes::from(sig.serialize())).pack())
+        .build();
+    witnesses[group_indices[0]] = witness.as_bytes();
+    tx.as_advanced_builder()
+        .set_witnesses(witnesses.into_iter().map(|w| w.pack()).collect())
+        .build()
+}
+
+#[test]
+fn test_sighash_all_message_of_interleaved_groups() {
+    let mut rng = thread_rng();
+    let mut data_loader = DummyDataLoader::new();
+    let privkey = Generator::random_privkey();
+    let privkey2 = Generator::random_privkey();
+    let pubkey_hash = blake160(&privkey.pubkey().expect("pubkey").serialize());
+    let pubkey_hash2 = blake160(&privkey2.pubkey().expect("pubkey").serialize());
+
+    let tx = gen_tx_with_grouped_args(
+        &mut data_loader,
+        vec![
+            (pubkey_hash.clone(), 1),
+            (pubkey_hash2, 2),
+            (pubkey_hash, 1),
+        ],
+        &mut rng,
+    );
+    // trailing witnesses are covered by both groups
+    let tx = tx
+        .as_advanced_builder()
+        .witness(Bytes::from(vec![42]).pack())
+        .build();
+    let tx = sign_group(tx, &privkey, &[0, 3]);
+    let tx = sign_group(tx, &privkey2, &[1, 2]);
+
+    let resolved_tx = Arc::new(build_resolved_tx(&data_loader, &tx));
+    let verify_result =
+        TransactionScriptsVerifier::new(resolved_tx, data_loader).verify(MAX_CYCLES);
+    verify_result.expect("pass verification");
+}
+
+#[test]
+fn test_sighash_all_message_errors() {
+    let mut rng = thread_rng();
+    let mut data_loader = DummyDataLoader::new();
+    let tx = gen_tx_with_grouped_args(&mut data_loader, vec![(vec![0; 20].into(), 2)], &mut rng);
+    assert_eq!(sighash_all_message(&tx, &[]), Err(MessageError::EmptyGroup));
+    assert_eq!(
+        sighash_all_message(&tx, &[2]),
+        Err(MessageError::MissingWitness(2))
+    );
+
+    let tx = tx
+        .as_advanced_builder()
+        .set_witnesses(vec![
+            Bytes::from(vec![1]).pack(),
+            Bytes::from(vec![0; 32769]).pack(),
+        ])
+        .build();
+    assert_eq!(
+        sighash_all_message(&tx, &[0, 1]),
+        Err(MessageError::InvalidWitnessArgs(0))
+    );
+    assert_eq!(
+        sighash_all_message(&tx, &[1]),
+        Err(MessageError::WitnessTooLarge(1))
+    );
+}
+
+fn verify_natively_and_in_vm(
+    data_loader: &DummyDataLoader,
+    tx: &TransactionView,
+    lock_script: &Script,
+) -> Result<(), SighashError> {
+    let resolved_tx = build_resolved_tx(data_loader, tx);
+    let native_result = verify_sighash_all(&resolved_tx, lock_script);
+    let verify_result = TransactionScriptsVerifier::new(Arc::new(resolved_tx), data_loader.clone())
+        .verify(MAX_CYCLES);
+    match native_result {
+        Ok(()) => {
+            verify_result.expect("pass verification");
+        }
+        Err(err) => assert_error_eq!(
+            verify_result.unwrap_err(),
+            ScriptError::validation_failure(lock_script, err.exit_code()).input_lock_script(0),
+        ),
+    }
+    native_result
+}
+
+fn with_first_lock(tx: &TransactionView, lock: Option<Bytes>) -> TransactionView {
+    let witness = WitnessArgs::new_unchecked(tx.witnesses().get(0).unwrap().unpack())
+        .as_builder()
+        .lock(lock.pack())
+        .build();
+    let mut witnesses: Vec<packed::Bytes> = tx.witnesses().into_iter().collect();
+    witnesses[0] = witness.as_bytes().pack();
+    tx.as_advanced_builder().set_witnesses(witnesses).build()
+}
+
+#[test]
+fn test_verify_sighash_all_matches_vm() {
+    let mut rng = thread_rng();
+    let mut data_loader = DummyDataLoader::new();
+    let privkey = Generator::random_privkey();
+    let pubkey_hash = blake160(&privkey.pubkey().expect("pubkey").serialize());
+    let lock_script = gen_lock_script(pubkey_hash.clone());
+    let tx = gen_tx_with_grouped_args(&mut data_loader, vec![(pubkey_hash, 2)], &mut rng);
+    let tx = tx
+        .as_advanced_builder()
+        .witness(Bytes::from(vec![42]).pack())
+        .build();
+    let signed_tx = sign_group(tx.clone(), &privkey, &[0, 1]);
+    let signature = WitnessArgs::new_unchecked(signed_tx.witnesses().get(0).unwrap().unpack())
+        .lock()
+        .to_opt()
+        .unwrap()
+        .raw_data();
+
+    assert_eq!(
+        verify_natively_and_in_vm(&data_loader, &signed_tx, &lock_script),
+        Ok(())
+    );
+    let wrong_key_tx = sign_group(tx.clone(), &Generator::random_privkey(), &[0, 1]);
+    assert_eq!(
+        verify_natively_and_in_vm(&data_loader, &wrong_key_tx, &lock_script),
+        Err(SighashError::PubkeyBlake160Hash)
+    );
+    let extra_witness_tx = signed_tx
+        .as_advanced_builder()
+        .witness(Bytes::new().pack())
+        .build();
+    assert_eq!(
+        verify_natively_and_in_vm(&data_loader, &extra_witness_tx, &lock_script),
+        Err(SighashError::PubkeyBlake160Hash)
+    );
+    let long_witness_tx = signed_tx
+        .as_advanced_builder()
+        .witness(Bytes::from(vec![0; 32769]).pack())
+        .build();
+    assert_eq!(
+        verify_natively_and_in_vm(&data_loader, &long_witness_tx, &lock_script),
+        Err(SighashError::WitnessSize)
+    );
+    let missing_witness_tx = signed_tx
+        .as_advanced_builder()
+        .set_witnesses(vec![])
+        .build();
+    assert_eq!(
+        verify_natively_and_in_vm(&data_loader, &missing_witness_tx, &lock_script),
+        Err(SighashError::Syscall)
+    );
+    assert_eq!(
+        verify_natively_and_in_vm(
+            &data_loader,
+            &with_first_lock(&signed_tx, None),
+            &lock_script
+        ),
+        Err(SighashError::Encoding)
+    );
+    let short_lock_tx = with_first_lock(&signed_tx, Some(signature.slice(..64)));
+    assert_eq!(
+        verify_natively_and_in_vm(&data_loader, &short_lock_tx, &lock_script),
+        Err(SighashError::ArgumentsLen)
+    );
+    let mut invalid_recid = signature.to_vec();
+    invalid_recid[64] = 4;
+    let invalid_recid_tx = with_first_lock(&signed_tx, Some(invalid_recid.into()));
+    assert_eq!(
+        verify_natively_and_in_vm(&data_loader, &invalid_recid_tx, &lock_script),
+        Err(SighashError::IllegalCallback)
+    );
+    let mut overflowing_r = signature.to_vec();
+    overflowing_r[..32].copy_from_slice(&[0xff; 32]);
+    let overflowing_r_tx = with_first_lock(&signed_tx, Some(overflowing_r.into()));
+    assert_eq!(
+        verify_natively_and_in_vm(&data_loader, &overflowing_r_tx, &lock_script),
+        Err(SighashError::SecpParseSignature)
+    );
+    let mut zero_r = signature.to_vec();
+    zero_r[..32].copy_from_slice(&[0; 32]);
+    let zero_r_tx = with_first_lock(&signed_tx, Some(zero_r.into()));
+    assert_eq!(
+        verify_natively_and_in_vm(&data_loader, &zero_r_tx, &lock_script),
+        Err(SighashError::SecpRecoverPubkey)
+    );
+
+    let long_args = Bytes::from(vec![0; 21]);
+    let long_args_lock = gen_lock_script(long_args.clone());
+    let tx = gen_tx_with_grouped_args(&mut data_loader, vec![(long_args, 1)], &mut rng);
+    let tx = sign_group(tx, &privkey, &[0]);
+    assert_eq!(
+        verify_natively_and_in_vm(&data_loader, &tx, &long_args_lock),
+        Err(SighashError::ArgumentsLen)
+    );
+}
@@ -0,0 +1,80 @@
+use super::{
+    blake160,
+    secp256k1_blake160_sighash_all::{
+        build_resolved_tx, gen_lock_script, gen_tx_with_grouped_args,
+    },
+    DummyDataLoader, MAX_CYCLES,
+};
+use crate::signer::{group_inputs, sign_tx, GroupSigner, SignError};
+use ckb_crypto::secp::Generator;
+use ckb_script::TransactionScriptsVerifier;
+use ckb_types::{bytes::Bytes, packed::WitnessArgs, prelude::*};
+use rand::thread_rng;
+use std::sync::Arc;
+
+#[test]
+fn test_group_interleaved_inputs() {
+    let mut rng = thread_rng();
+    let mut data_loader = DummyDataLoader::new();
+    let args: Bytes = vec![1; 20].into();
+    let args2: Bytes = vec![2; 20].into();
+    let tx = gen_tx_with_grouped_args(
+        &mut data_loader,
+        vec![(args.clone(), 1), (args2.clone(), 2), (args.clone(), 1)],
+        &mut rng,
+    );
+
+    let groups = group_inputs(&tx, &data_loader).expect("group inputs");
+    assert_eq!(groups.len(), 2);
+    assert_eq!(groups[0].lock, gen_lock_script(args));
+    assert_eq!(groups[0].input_indices, vec![0, 3]);
+    assert_eq!(groups[1].lock, gen_lock_script(args2));
+    assert_eq!(groups[1].input_indices, vec![1, 2]);
+
+    assert_eq!(
+        group_inputs(&tx, &DummyDataLoader::new()),
+        Err(SignError::InputCellNotFound(0))
+    );
+}
+
+#[test]
+fn test_sign_interleaved_groups() {
+    let mut rng = thread_rng();
+    let mut data_loader = DummyDataLoader::new();
+    let privkey = Generator::random_privkey();
+    let privkey2 = Generator::random_privkey();
+    let pubkey_hash = blake160(&privkey.pubkey().expect("pubkey").serialize());
+    let pubkey_hash2 = blake160(&privkey2.pubkey().expect("pubkey").serialize());
+    let tx = gen_tx_with_grouped_args(
+        &mut data_loader,
+        vec![
+            (pubkey_hash.clone(), 1),
+            (pubkey_hash2.clone(), 2),
+            (pubkey_hash, 1),
+        ],
+        &mut rng,
+    );
+    // no witnesses at all, the signer creates the placeholders
+    let tx = tx.as_advanced_builder().set_witnesses(vec![]).build();
+
+    let tx = sign_tx(&tx, &data_loader, &privkey).expect("sign");
+    assert_eq!(tx.witnesses().len(), 4);
+    assert!(tx.witnesses().get(1).unwrap().is_empty());
+
+    let lock2 = gen_lock_script(pubkey_hash2);
+    assert!(privkey2.owns(&lock2));
+    assert!(!privkey.owns(&lock2));
+    let tx = sign_tx(&tx, &data_loader, &privkey2).expect("sign");
+    for index in &[0, 1] {
+        let witness = WitnessArgs::from_slice(&tx.witnesses().get(*index).unwrap().raw_data())
+            .expect("witness args");
+        assert_eq!(witness.lock().to_opt().unwrap().raw_data().len(), 65);
+    }
+    assert!(tx.witnesses().get(2).unwrap().is_empty());
+    assert!(tx.witnesses().get(3).unwrap().is_empty());
+
+    let resolved_tx = Arc::new(build_resolved_tx(&data_loader, &tx));
+    let verify_result =
+        TransactionScriptsVerifier::new(resolved_tx, data_loader).verify(MAX_CYCLES);
+    verify_result.expect("pass verification");
+}
@@ -0,0 +1,119 @@
+use crate::since::{EpochNumberWithFraction, Since, SinceMetric};
+use ckb_types::core;
+use std::cmp::Ordering;
+
+fn epoch(number: u64, index: u64, length: u64) -> EpochNumberWithFraction {
+    EpochNumberWithFraction::new(number, index, length).unwrap()
+}
+
+#[test]
+fn test_epoch_number_with_fraction() {
+    let value = epoch(200, 5, 100);
+    assert_eq!(
+        value.full_value(),
+        core::EpochNumberWithFraction::new(200, 5, 100).full_value()
+    );
+    assert_eq!(
+        (value.number(), value.index(), value.length()),
+        (200, 5, 100)
+    );
+    assert_eq!(
+        EpochNumberWithFraction::from_full_value(value.full_value() | 0xff << 56),
+        value
+    );
+    assert_eq!(EpochNumberWithFraction::new(1 << 24, 0, 1), None);
+    assert_eq!(EpochNumberWithFraction::new(1, 1 << 16, 1), None);
+    assert_eq!(EpochNumberWithFraction::new(1, 0, 1 << 16), None);
+    assert_eq!(EpochNumberWithFraction::new(1, 2, 2), None);
+
+    assert_eq!(
+        epoch(1, 0, 100).cmp_epoch(epoch(0, 99, 100)),
+        Ordering::Greater
+    );
+    assert_eq!(epoch(1, 1, 3).cmp_epoch(epoch(1, 1, 2)), Ordering::Less);
+    // Equivalent fractions compare equal, but only the same packed values are equal.
+    assert_eq!(epoch(1, 1, 2).cmp_epoch(epoch(1, 2, 4)), Ordering::Equal);
+    assert_ne!(epoch(1, 1, 2), epoch(1, 2, 4));
+}
+
+#[test]
+fn test_since_constructors() {
+    let epoch = epoch(200, 5, 100);
+    let cases = vec![
+        (
+            Since::absolute_block_number(42).unwrap(),
+            42,
+            SinceMetric::BlockNumber(42),
+        ),
+        (
+            Since::relative_block_number(42).unwrap(),
+            0x8000_0000_0000_002a,
+            SinceMetric::BlockNumber(42),
+        ),
+        (
+            Since::absolute_epoch(epoch),
+            0x2000_0000_0000_0000 | epoch.full_value(),
+            SinceMetric::EpochNumberWithFraction(epoch),
+        ),
+        (
+            Since::relative_epoch(epoch),
+            0xa000_0000_0000_0000 | epoch.full_value(),
+            SinceMetric::EpochNumberWithFraction(epoch),
+        ),
+        (
+            Since::absolute_timestamp(1_600_000_000).unwrap(),
+            0x4000_0000_5f5e_1000,
+            SinceMetric::Timestamp(1_600_000_000),
+        ),
+        (
+            Since::relative_timestamp(3600).unwrap(),
+            0xc000_0000_0000_0e10,
+            SinceMetric::Timestamp(3600),
+        ),
+    ];
+    for (since, raw, metric) in cases {
+        assert_eq!(since.as_u64(), raw);
+        assert_eq!(since.metric(), Some(metric));
+        assert_eq!(since.is_relative(), raw >> 63 == 1);
+        assert_eq!(Since::new(metric, since.is_relative()), Some(since));
+    }
+
+    assert_eq!(Since::absolute_block_number(1 << 56), None);
+    assert_eq!(Since::from_raw_value(0x0100_0000_0000_0000).metric(), None);
+    assert_eq!(Since::from_raw_value(0x6000_0000_0000_0000).metric(), None);
+}
+
+#[test]
+fn test_since_comparison() {
+    let absolute = |number, index, length| Since::absolute_epoch(epoch(number, index, length));
+    assert_eq!(
+        absolute(1, 1, 2).cmp_since(absolute(1, 1, 3)),
+        Some(Ordering::Greater)
+    );
+    assert_eq!(
+        absolute(1, 1, 2).cmp_since(absolute(1, 2, 4)),
+        Some(Ordering::Equal)
+    );
+    assert!(absolute(1, 1, 2).satisfies(absolute(1, 2, 4)));
+    assert!(!absolute(1, 1, 3).satisfies(absolute(1, 1, 2)));
+    assert!(absolute(2, 0, 1).satisfies(absolute(1, 1, 2)));
+
+    // relative epochs are compared by the packed value, like `check_since`
+    let relative = |number, index, length| Since::relative_epoch(epoch(number, index, length));
+    assert_eq!(
+        relative(1, 1, 3).cmp_since(relative(1, 1, 2)),
+        Some(Ordering::Greater)
+    );
+    assert!(relative(1, 1, 3).satisfies(relative(1, 1, 2)));
+
+    let block = Since::absolute_block_number(100).unwrap();
+    assert!(Since::absolute_block_number(101).unwrap().satisfies(block));
+    assert!(!Since::absolute_block_number(99).unwrap().satisfies(block));
+    assert_eq!(
+        block.cmp_since(Since::relative_block_number(100).unwrap()),
+        None
+    );
+    assert!(!Since::relative_block_number(100).unwrap().satisfies(block));
+    assert!(!absolute(1, 0, 1).satisfies(Since::default()));
+    assert!(Since::from_raw_value(7).satisfies(Since::default()));
+}
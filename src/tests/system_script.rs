@@ -0,0 +1,77 @@
+use super::{DAO_BIN, MULTISIG_ALL_BIN, SECP256K1_DATA_BIN, SIGHASH_ALL_BIN};
+use crate::{get_binary, SystemScript, BUNDLED_CELL, CODE_HASH_DAO};
+use ckb_types::bytes::Bytes;
+
+#[test]
+fn test_bundled_binaries() {
+    let expected: &[(SystemScript, &Bytes)] = &[
+        (SystemScript::Sighash, &SIGHASH_ALL_BIN),
+        (SystemScript::Multisig, &MULTISIG_ALL_BIN),
+        (SystemScript::Dao, &DAO_BIN),
+        (SystemScript::Secp256k1Data, &SECP256K1_DATA_BIN),
+    ];
+    for (script, bin) in expected {
+        assert_eq!(&script.binary()[..], &bin[..], "{}", script);
+        assert_eq!(script.size(), bin.len(), "{}", script);
+        assert_eq!(
+            script.data_hash(),
+            ckb_hash::blake2b_256(&bin[..]),
+            "{}",
+            script
+        );
+    }
+    assert_eq!(SystemScript::Dao.data_hash(), CODE_HASH_DAO);
+}
+
+#[test]
+fn test_iter_all_bundled() {
+    let names: Vec<_> = SystemScript::iter().map(|script| script.name()).collect();
+    assert_eq!(names.len(), 4);
+    for path in BUNDLED_CELL.file_names() {
+        assert!(
+            SystemScript::iter().any(|script| script.path() == path),
+            "{} is not covered",
+            path
+        );
+    }
+}
+
+#[test]
+fn test_from_name() {
+    for script in SystemScript::iter() {
+        assert_eq!(SystemScript::from_name(script.name()), Some(script));
+        assert_eq!(script.to_string(), script.name());
+    }
+    assert_eq!(SystemScript::from_name("specs/cells/dao"), None);
+}
+
+#[test]
+fn test_get_binary_cached() {
+    for script in SystemScript::iter() {
+        let first = get_binary(script);
+        let second = get_binary(script);
+        assert_eq!(&first[..], &script.binary()[..], "{}", script);
+        // both calls share the same decompressed buffer
+        assert_eq!(first.as_ptr(), second.as_ptr(), "{}", script);
+    }
+    assert_eq!(get_binary(SystemScript::Dao), *DAO_BIN);
+}
+
+#[test]
+fn test_elf_metadata() {
+    for script in SystemScript::iter() {
+        let metadata = match script.elf_metadata() {
+            Some(metadata) => metadata,
+            None => {
+                assert_eq!(script, SystemScript::Secp256k1Data);
+                continue;
+            }
+        };
+        let binary = get_binary(script);
+        assert_eq!(&binary[..4], b"\x7fELF", "{}", script);
+        assert!(metadata.text_size > 0, "{}", script);
+        assert!(metadata.text_size < binary.len() as u64, "{}", script);
+        assert!(metadata.entry_point > 0, "{}", script);
+        assert!(metadata.stripped, "{}", script);
+    }
+}
@@ -0,0 +1,80 @@
+use crate::{
+    type_id::{type_id_args, type_id_script, type_id_type_hash, TYPE_ID_CODE_HASH},
+    SystemScript, TYPE_HASH_DAO, TYPE_HASH_SECP256K1_BLAKE160_MULTISIG_ALL,
+    TYPE_HASH_SECP256K1_BLAKE160_SIGHASH_ALL,
+};
+use ckb_types::{
+    core::ScriptHashType,
+    packed::{CellInput, OutPoint},
+    prelude::*,
+};
+
+#[test]
+fn test_type_id_code_hash() {
+    assert_eq!(&TYPE_ID_CODE_HASH[25..], b"TYPE_ID");
+    assert!(TYPE_ID_CODE_HASH[..25].iter().all(|b| *b == 0));
+}
+
+#[test]
+fn test_genesis_type_hashes() {
+    let expected = [
+        (
+            SystemScript::Sighash,
+            TYPE_HASH_SECP256K1_BLAKE160_SIGHASH_ALL,
+        ),
+        (SystemScript::Dao, TYPE_HASH_DAO),
+        (
+            SystemScript::Multisig,
+            TYPE_HASH_SECP256K1_BLAKE160_MULTISIG_ALL,
+        ),
+    ];
+    for (script, type_hash) in expected.iter() {
+        let type_script = script.genesis_type_script().expect("type script");
+        assert_eq!(type_script.calc_script_hash(), type_hash.pack());
+        assert_eq!(script.type_hash(), Some(*type_hash));
+        assert_eq!(
+            type_id_type_hash(
+                &CellInput::new_cellbase_input(0),
+                script.genesis_output_index()
+            ),
+            *type_hash
+        );
+    }
+    assert!(SystemScript::Secp256k1Data.genesis_type_script().is_none());
+    assert!(SystemScript::Secp256k1Data.type_hash().is_none());
+}
+
+#[test]
+fn test_type_id_args() {
+    let input = CellInput::new(OutPoint::new([1u8; 32].pack(), 3), 0);
+    let args = {
+        let mut blake2b = ckb_hash::new_blake2b();
+        blake2b.update(input.as_slice());
+        blake2b.update(&5u64.to_le_bytes());
+        let mut ret = [0u8; 32];
+        blake2b.finalize(&mut ret);
+        ret
+    };
+    assert_eq!(type_id_args(&input, 5), args);
+    assert_ne!(type_id_args(&input, 4), args);
+
+    let script = type_id_script(&input, 5);
+    assert_eq!(script.code_hash(), TYPE_ID_CODE_HASH.pack());
+    assert_eq!(script.hash_type(), ScriptHashType::Type.into());
+    assert_eq!(&script.args().raw_data()[..], &args[..]);
+}
+
+#[test]
+fn test_code_hash_by_hash_type() {
+    for script in SystemScript::iter() {
+        assert_eq!(
+            script.code_hash(ScriptHashType::Data),
+            Some(script.data_hash())
+        );
+        assert_eq!(
+            script.code_hash(ScriptHashType::Data1),
+            Some(script.data_hash())
+        );
+        assert_eq!(script.code_hash(ScriptHashType::Type), script.type_hash());
+    }
+}
@@ -0,0 +1,50 @@
+use crate::{
+    versions::{all_versions, find_version},
+    SystemScript,
+};
+use std::collections::HashSet;
+
+#[test]
+fn test_current_versions() {
+    for script in SystemScript::iter() {
+        let version = script.current_version();
+        assert_eq!(version.script(), script);
+        assert!(version.is_current(), "{}", script);
+        assert!(version.is_bundled(), "{}", script);
+        assert_eq!(version.binary().unwrap(), crate::get_binary(script));
+        assert_eq!(script.versions().next(), Some(version));
+    }
+    assert_eq!(SystemScript::Dao.current_version().tag, "v0.6.0");
+}
+
+#[test]
+fn test_find_historical_version() {
+    let data_hash = [
+        0x32, 0x06, 0x4a, 0x14, 0xce, 0x10, 0xd9, 0x5d, 0x4b, 0x73, 0x43, 0x05, 0x4c, 0xc1, 0x9d,
+        0x73, 0xb2, 0x5b, 0x16, 0xae, 0x61, 0xa6, 0xc6, 0x81, 0x01, 0x1c, 0xa7, 0x81, 0xa6, 0x0c,
+        0x79, 0x23,
+    ];
+    let version = find_version(&data_hash).expect("registered version");
+    assert_eq!(version.script(), SystemScript::Dao);
+    assert_eq!(version.tag, "v0.5.1");
+    assert!(!version.is_current());
+    assert_eq!(SystemScript::Dao.versions().count(), 3);
+    assert_eq!(SystemScript::Multisig.versions().count(), 3);
+
+    assert!(find_version(&[0u8; 32]).is_none());
+}
+
+#[test]
+fn test_historical_binaries() {
+    let mut hashes = HashSet::new();
+    for version in all_versions() {
+        assert!(hashes.insert(version.data_hash), "{}", version.tag);
+        if version.is_current() {
+            continue;
+        }
+        assert_eq!(version.is_bundled(), cfg!(feature = "history"));
+        if let Some(binary) = version.binary() {
+            assert_eq!(ckb_hash::blake2b_256(&binary), version.data_hash);
+        }
+    }
+}
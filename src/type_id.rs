@@ -0,0 +1,129 @@
+//! Type ID of the system scripts deployed in the genesis block.
+//!
+//! A cell created with a Type ID type script keeps the same type hash when its data is
+//! upgraded, so on mainnet and testnet the system scripts are usually referenced with
+//! `hash_type: type` and the type hash as `code_hash`.
+
+use crate::SystemScript;
+use ckb_hash::new_blake2b;
+use ckb_types::{
+    bytes::Bytes,
+    core::ScriptHashType,
+    packed::{CellInput, Script},
+    prelude::*,
+    H256,
+};
+
+/// `code_hash` of the builtin Type ID script, `"TYPE_ID"` right aligned in 32 bytes.
+pub const TYPE_ID_CODE_HASH: [u8; 32] = [
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x54, 0x59, 0x50, 0x45, 0x5f, 0x49, 0x44,
+];
+
+/// Type hash of `secp256k1_blake160_sighash_all` deployed in the mainnet and testnet genesis.
+pub const TYPE_HASH_SECP256K1_BLAKE160_SIGHASH_ALL: [u8; 32] = [
+    0x9b, 0xd7, 0xe0, 0x6f, 0x3e, 0xcf, 0x4b, 0xe0, 0xf2, 0xfc, 0xd2, 0x18, 0x8b, 0x23, 0xf1, 0xb9,
+    0xfc, 0xc8, 0x8e, 0x5d, 0x4b, 0x65, 0xa8, 0x63, 0x7b, 0x17, 0x72, 0x3b, 0xbd, 0xa3, 0xcc, 0xe8,
+];
+
+/// Type hash of `dao` deployed in the mainnet and testnet genesis.
+pub const TYPE_HASH_DAO: [u8; 32] = [
+    0x82, 0xd7, 0x6d, 0x1b, 0x75, 0xfe, 0x2f, 0xd9, 0xa2, 0x7d, 0xfb, 0xaa, 0x65, 0xa0, 0x39, 0x22,
+    0x1a, 0x38, 0x0d, 0x76, 0xc9, 0x26, 0xf3, 0x78, 0xd3, 0xf8, 0x1c, 0xf3, 0xe7, 0xe1, 0x3f, 0x2e,
+];
+
+/// Type hash of `secp256k1_blake160_multisig_all` deployed in the mainnet and testnet genesis.
+pub const TYPE_HASH_SECP256K1_BLAKE160_MULTISIG_ALL: [u8; 32] = [
+    0x5c, 0x50, 0x69, 0xeb, 0x08, 0x57, 0xef, 0xc6, 0x5e, 0x1b, 0xca, 0x0c, 0x07, 0xdf, 0x34, 0xc3,
+    0x16, 0x63, 0xb3, 0x62, 0x2f, 0xd3, 0x87, 0x6c, 0x87, 0x63, 0x20, 0xfc, 0x96, 0x34, 0xe2, 0xa8,
+];
+
+/// Calculates the Type ID script args of the cell created at `output_index` of a transaction
+/// whose first input is `first_input`.
+pub fn type_id_args(first_input: &CellInput, output_index: u64) -> [u8; 32] {
+    let mut blake2b = new_blake2b();
+    blake2b.update(first_input.as_slice());
+    blake2b.update(&output_index.to_le_bytes());
+    let mut args = [0u8; 32];
+    blake2b.finalize(&mut args);
+    args
+}
+
+/// Builds the Type ID type script of the cell created at `output_index` of a transaction
+/// whose first input is `first_input`.
+pub fn type_id_script(first_input: &CellInput, output_index: u64) -> Script {
+    Script::new_builder()
+        .code_hash(TYPE_ID_CODE_HASH.pack())
+        .hash_type(ScriptHashType::Type.into())
+        .args(Bytes::from(type_id_args(first_input, output_index).to_vec()).pack())
+        .build()
+}
+
+/// Calculates the type hash of the cell created at `output_index` of a transaction whose
+/// first input is `first_input`, which is the `code_hash` to reference it via
+/// `hash_type: type`.
+pub fn type_id_type_hash(first_input: &CellInput, output_index: u64) -> [u8; 32] {
+    let hash: H256 = type_id_script(first_input, output_index)
+        .calc_script_hash()
+        .unpack();
+    hash.0
+}
+
+/// Builds the Type ID type script of the genesis cellbase output at `output_index`.
+pub fn genesis_type_id_script(output_index: u64) -> Script {
+    type_id_script(&CellInput::new_cellbase_input(0), output_index)
+}
+
+impl SystemScript {
+    /// Output index of the script cell in the genesis cellbase transaction.
+    pub fn genesis_output_index(self) -> u64 {
+        match self {
+            #[cfg(feature = "sighash")]
+            SystemScript::Sighash => 1,
+            #[cfg(feature = "dao")]
+            SystemScript::Dao => 2,
+            #[cfg(feature = "secp256k1-data")]
+            SystemScript::Secp256k1Data => 3,
+            #[cfg(feature = "multisig")]
+            SystemScript::Multisig => 4,
+        }
+    }
+
+    /// Type ID type script of the genesis cell, `None` for `secp256k1_data` which is deployed
+    /// without a Type ID.
+    pub fn genesis_type_script(self) -> Option<Script> {
+        match self {
+            #[cfg(feature = "sighash")]
+            SystemScript::Sighash => Some(genesis_type_id_script(self.genesis_output_index())),
+            #[cfg(feature = "multisig")]
+            SystemScript::Multisig => Some(genesis_type_id_script(self.genesis_output_index())),
+            #[cfg(feature = "dao")]
+            SystemScript::Dao => Some(genesis_type_id_script(self.genesis_output_index())),
+            #[cfg(feature = "secp256k1-data")]
+            SystemScript::Secp256k1Data => None,
+        }
+    }
+
+    /// Type hash of the genesis cell on mainnet and testnet.
+    pub fn type_hash(self) -> Option<[u8; 32]> {
+        match self {
+            #[cfg(feature = "sighash")]
+            SystemScript::Sighash => Some(TYPE_HASH_SECP256K1_BLAKE160_SIGHASH_ALL),
+            #[cfg(feature = "multisig")]
+            SystemScript::Multisig => Some(TYPE_HASH_SECP256K1_BLAKE160_MULTISIG_ALL),
+            #[cfg(feature = "dao")]
+            SystemScript::Dao => Some(TYPE_HASH_DAO),
+            #[cfg(feature = "secp256k1-data")]
+            SystemScript::Secp256k1Data => None,
+        }
+    }
+
+    /// `code_hash` to reference the script with `hash_type`: the data hash for `data` and
+    /// `data1`, the genesis type hash for `type`.
+    pub fn code_hash(self, hash_type: ScriptHashType) -> Option<[u8; 32]> {
+        match hash_type {
+            ScriptHashType::Data | ScriptHashType::Data1 => Some(self.data_hash()),
+            ScriptHashType::Type => self.type_hash(),
+        }
+    }
+}
@@ -0,0 +1,311 @@
+use super::MAX_SCRIPT_SIZE;
+use crate::{
+    dao::{accumulate_rate, header_epoch, lock_epochs, maximum_withdraw},
+    script_error::DaoError,
+    sighash::MAX_WITNESS_SIZE,
+    since::{EpochNumberWithFraction, Since, SINCE_EPOCH_FRACTION_FLAG},
+};
+use ckb_traits::{CellDataProvider, HeaderProvider};
+use ckb_types::{
+    core::{cell::ResolvedTransaction, HeaderView},
+    packed::{Byte32, CellOutput, Script, WitnessArgs},
+    prelude::*,
+};
+use std::convert::TryInto;
+
+const DEPOSIT_HEADER_INDEX_SIZE: usize = 8;
+
+/// Verifies the cells of `rtx` typed by `dao_script` like `c/dao.c`.
+///
+/// The cell data and headers are loaded from `provider`, as `TransactionScriptsVerifier` does,
+/// and the header of an input is only visible when its block hash is in the header deps.
+/// `dao_script` is assumed to run the DAO script, its code hash is not checked.
+pub fn verify_dao<P: CellDataProvider + HeaderProvider>(
+    rtx: &ResolvedTransaction,
+    dao_script: &Script,
+    provider: &P,
+) -> Result<(), DaoError> {
+    if dao_script.as_slice().len() > MAX_SCRIPT_SIZE {
+        return Err(DaoError::ScriptTooLong);
+    }
+    if !dao_script.args().raw_data().is_empty() {
+        return Err(DaoError::WrongNumberOfArguments);
+    }
+    let verifier = DaoVerifier {
+        rtx,
+        provider,
+        dao_script_hash: dao_script.calc_script_hash(),
+    };
+
+    // Inputs and outputs are visited side by side, so a deposited input is checked before the
+    // withdrawing output at the same index.
+    let mut input_capacities = 0u64;
+    let mut output_capacities = 0u64;
+    let mut inputs_exhausted = false;
+    let mut outputs_exhausted = false;
+    let mut index = 0;
+    while !(inputs_exhausted && outputs_exhausted) {
+        let mut output_withdrawing = false;
+        if !inputs_exhausted {
+            match verifier.input_capacity(index)? {
+                Some((capacity, withdrawing)) => {
+                    input_capacities = input_capacities
+                        .checked_add(capacity)
+                        .ok_or(DaoError::Overflow)?;
+                    output_withdrawing = withdrawing;
+                }
+                None => inputs_exhausted = true,
+            }
+        }
+        if !outputs_exhausted {
+            outputs_exhausted =
+                !verifier.validate_output(index, output_withdrawing, &mut output_capacities)?;
+        }
+        index += 1;
+    }
+
+    if output_capacities > input_capacities {
+        return Err(DaoError::IncorrectCapacity);
+    }
+    Ok(())
+}
+
+/// The fields of a header used by the DAO script.
+struct DaoHeader {
+    block_number: u64,
+    epoch: EpochNumberWithFraction,
+    accumulate_rate: u64,
+}
+
+impl DaoHeader {
+    fn new(header: &HeaderView) -> Result<Self, DaoError> {
+        Ok(DaoHeader {
+            block_number: header.number(),
+            epoch: header_epoch(header)?,
+            accumulate_rate: accumulate_rate(header),
+        })
+    }
+}
+
+struct DaoVerifier<'a, P> {
+    rtx: &'a ResolvedTransaction,
+    provider: &'a P,
+    dao_script_hash: Byte32,
+}
+
+impl<'a, P: CellDataProvider + HeaderProvider> DaoVerifier<'a, P> {
+    fn is_dao_cell(&self, output: &CellOutput) -> bool {
+        output
+            .type_()
+            .to_opt()
+            .map(|type_script| type_script.calc_script_hash() == self.dao_script_hash)
+            .unwrap_or(false)
+    }
+
+    /// The capacity counted for the input at `index`, and whether it is a deposited cell
+    /// withdrawn by the output at the same index. `None` when the inputs are exhausted.
+    fn input_capacity(&self, index: usize) -> Result<Option<(u64, bool)>, DaoError> {
+        let cell = match self.rtx.resolved_inputs.get(index) {
+            Some(cell) => cell,
+            None => return Ok(None),
+        };
+        let capacity: u64 = cell.cell_output.capacity().unpack();
+        if !self.is_dao_cell(&cell.cell_output) {
+            return Ok(Some((capacity, false)));
+        }
+        let data = self
+            .provider
+            .load_cell_data(cell)
+            .ok_or(DaoError::ItemMissing)?;
+        // A deposited cell keeps zero, a withdrawing cell keeps the deposit block number.
+        match read_u64(&data)? {
+            0 => {
+                self.validate_withdrawing_cell(index, capacity)?;
+                Ok(Some((capacity, true)))
+            }
+            block_number => {
+                let capacity = self.withdraw_capacity(index, block_number, capacity)?;
+                Ok(Some((capacity, false)))
+            }
+        }
+    }
+
+    /// Adds the capacity of the output at `index`, `false` when the outputs are exhausted.
+    fn validate_output(
+        &self,
+        index: usize,
+        output_withdrawing: bool,
+        output_capacities: &mut u64,
+    ) -> Result<bool, DaoError> {
+        let tx = &self.rtx.transaction;
+        let output = match tx.outputs().get(index) {
+            Some(output) => output,
+            None => return Ok(false),
+        };
+        let capacity: u64 = output.capacity().unpack();
+        *output_capacities = output_capacities
+            .checked_add(capacity)
+            .ok_or(DaoError::Overflow)?;
+        // Withdrawing cells are checked with their deposited cells, new DAO cells must be
+        // deposits.
+        if self.is_dao_cell(&output) && !output_withdrawing {
+            let data = tx.outputs_data().get(index).unwrap_or_default().raw_data();
+            if read_u64(&data)? != 0 {
+                return Err(DaoError::NewlyCreatedCell);
+            }
+        }
+        Ok(true)
+    }
+
+    /// Checks that the output at `index` withdraws the deposited input at the same index.
+    fn validate_withdrawing_cell(&self, index: usize, capacity: u64) -> Result<(), DaoError> {
+        let tx = &self.rtx.transaction;
+        let output = tx.outputs().get(index).ok_or(DaoError::IndexOutOfBound)?;
+        let type_hash = output
+            .type_()
+            .to_opt()
+            .ok_or(DaoError::ItemMissing)?
+            .calc_script_hash();
+        if type_hash != self.dao_script_hash {
+            return Err(DaoError::InvalidWithdrawingCell);
+        }
+        let output_capacity: u64 = output.capacity().unpack();
+        if output_capacity != capacity {
+            return Err(DaoError::InvalidWithdrawingCell);
+        }
+        let deposit_header = self.input_header(index)?;
+        let data = tx.outputs_data().get(index).unwrap_or_default().raw_data();
+        if read_u64(&data)? != deposit_header.block_number {
+            return Err(DaoError::InvalidWithdrawingCell);
+        }
+        Ok(())
+    }
+
+    /// The maximum capacity withdrawn from the withdrawing input at `index`.
+    fn withdraw_capacity(
+        &self,
+        index: usize,
+        deposited_block_number: u64,
+        capacity: u64,
+    ) -> Result<u64, DaoError> {
+        let header_dep_index = self.deposit_header_index(index)?;
+        let deposit_hash = self
+            .rtx
+            .transaction
+            .header_deps()
+            .get(header_dep_index)
+            .ok_or(DaoError::IndexOutOfBound)?;
+        let deposit_header = self
+            .provider
+            .get_header(&deposit_hash)
+            .ok_or(DaoError::ItemMissing)
+            .and_then(|header| DaoHeader::new(&header))?;
+        if deposited_block_number != deposit_header.block_number {
+            return Err(DaoError::InvalidWithdrawBlock);
+        }
+        let withdraw_header = self.input_header(index)?;
+
+        let (deposit, withdraw) = (deposit_header.epoch, withdraw_header.epoch);
+        // The minimal since is not necessarily a valid epoch, its number may overflow 24 bits.
+        let minimal_number = deposit.number() + lock_epochs(deposit, withdraw)?;
+        let since = Since::from_raw_value(
+            self.rtx
+                .transaction
+                .inputs()
+                .get(index)
+                .ok_or(DaoError::IndexOutOfBound)?
+                .since()
+                .unpack(),
+        );
+        if since.flags() != SINCE_EPOCH_FRACTION_FLAG {
+            return Err(DaoError::IncorrectSince);
+        }
+        let since_epoch = EpochNumberWithFraction::from_full_value(since.value());
+        // A zero length since is the start of the epoch.
+        let (since_index, since_length) = if since_epoch.length() == 0 {
+            (0, 1)
+        } else {
+            (since_epoch.index(), since_epoch.length())
+        };
+        if since_index >= since_length {
+            return Err(DaoError::IncorrectEpoch);
+        }
+        let minimal_fraction = deposit.index() * since_length;
+        let since_fraction = since_index * deposit.length();
+        if since_epoch.number() < minimal_number
+            || (since_epoch.number() == minimal_number && since_fraction < minimal_fraction)
+        {
+            return Err(DaoError::IncorrectSince);
+        }
+
+        let cell = &self.rtx.resolved_inputs[index];
+        let occupied_capacity = cell
+            .occupied_capacity()
+            .map_err(|_| DaoError::Syscall)?
+            .as_u64();
+        maximum_withdraw(
+            capacity,
+            occupied_capacity,
+            deposit_header.accumulate_rate,
+            withdraw_header.accumulate_rate,
+        )
+    }
+
+    /// The header dep index of the deposit block, kept in the `input_type` of the witness.
+    fn deposit_header_index(&self, index: usize) -> Result<usize, DaoError> {
+        let witness = self
+            .rtx
+            .transaction
+            .witnesses()
+            .get(index)
+            .ok_or(DaoError::Syscall)?
+            .raw_data();
+        if witness.len() > MAX_WITNESS_SIZE {
+            return Err(DaoError::WitnessTooLong);
+        }
+        let input_type = WitnessArgs::from_slice(&witness)
+            .ok()
+            .and_then(|witness_args| witness_args.input_type().to_opt())
+            .map(|input_type| input_type.raw_data())
+            .ok_or(DaoError::Encoding)?;
+        if input_type.len() != DEPOSIT_HEADER_INDEX_SIZE {
+            return Err(DaoError::Encoding);
+        }
+        // The script only reads the first byte of the little endian index.
+        Ok(usize::from(input_type[0]))
+    }
+
+    /// The header of the block committing the input at `index`, which must be a header dep.
+    fn input_header(&self, index: usize) -> Result<DaoHeader, DaoError> {
+        let cell = self
+            .rtx
+            .resolved_inputs
+            .get(index)
+            .ok_or(DaoError::IndexOutOfBound)?;
+        let block_hash = cell
+            .transaction_info
+            .as_ref()
+            .map(|info| info.block_hash.clone())
+            .ok_or(DaoError::ItemMissing)?;
+        if !self
+            .rtx
+            .transaction
+            .header_deps()
+            .into_iter()
+            .any(|hash| hash == block_hash)
+        {
+            return Err(DaoError::ItemMissing);
+        }
+        let header = self
+            .provider
+            .get_header(&block_hash)
+            .ok_or(DaoError::ItemMissing)?;
+        DaoHeader::new(&header)
+    }
+}
+
+/// Reads cell data which must be a 64-bit little endian integer.
+fn read_u64(data: &[u8]) -> Result<u64, DaoError> {
+    let data: [u8; 8] = data.try_into().map_err(|_| DaoError::Syscall)?;
+    Ok(u64::from_le_bytes(data))
+}
@@ -0,0 +1,95 @@
+//! Native implementations of the bundled scripts.
+//!
+//! The verifiers check a script group of a resolved transaction with the same rules as the C
+//! scripts, and fail with the same exit codes, without running the binaries in CKB-VM. They
+//! serve as fast offline pre-checks, and as references in differential tests against
+//! `TransactionScriptsVerifier`.
+//!
+//! Only the transaction is checked. The secp256k1 data cell dep and the cycles consumed by
+//! the scripts are left to the VM.
+//!
+//! The NervosDAO verifier also needs the cell data and the headers of the chain, loaded from
+//! the same providers as `TransactionScriptsVerifier`.
+
+mod dao;
+mod multisig;
+mod sighash;
+
+pub use dao::verify_dao;
+pub use multisig::verify_multisig_all;
+pub use sighash::verify_sighash_all;
+
+use crate::sighash::{blake160, MessageError, BLAKE160_SIZE, MAX_WITNESS_SIZE};
+use ckb_crypto::secp::Signature;
+use ckb_types::{
+    bytes::Bytes,
+    core::{cell::ResolvedTransaction, TransactionView},
+    packed::{Script, WitnessArgs},
+    prelude::*,
+    H256,
+};
+
+/// Scripts larger than this are rejected by the locks.
+const MAX_SCRIPT_SIZE: usize = 32768;
+const RECID_INDEX: usize = 64;
+const MAX_RECID: u8 = 3;
+
+/// Failures of libsecp256k1 while recovering a pubkey.
+enum SecpError {
+    /// The illegal callback aborts on a recovery id out of range.
+    IllegalCallback,
+    ParseSignature,
+    RecoverPubkey,
+}
+
+/// Indices of the inputs of `rtx` locked by `lock`, the `CKB_SOURCE_GROUP_INPUT` of the lock.
+fn group_input_indices(rtx: &ResolvedTransaction, lock: &Script) -> Vec<usize> {
+    let lock_hash = lock.calc_script_hash();
+    rtx.resolved_inputs
+        .iter()
+        .enumerate()
+        .filter(|(_, cell)| cell.cell_output.lock().calc_script_hash() == lock_hash)
+        .map(|(index, _)| index)
+        .collect()
+}
+
+/// The `lock` of the first group witness, like `extract_witness_lock` of `c/common.h`.
+///
+/// A missing `lock` is reported as an invalid `WitnessArgs`, which the locks don't tell apart.
+fn first_witness_lock(
+    tx: &TransactionView,
+    group_indices: &[usize],
+) -> Result<Bytes, MessageError> {
+    let first = *group_indices.first().ok_or(MessageError::EmptyGroup)?;
+    let witness = tx
+        .witnesses()
+        .get(first)
+        .ok_or(MessageError::MissingWitness(first))?
+        .raw_data();
+    if witness.len() > MAX_WITNESS_SIZE {
+        return Err(MessageError::WitnessTooLarge(first));
+    }
+    WitnessArgs::from_slice(&witness)
+        .ok()
+        .and_then(|witness_args| witness_args.lock().to_opt())
+        .map(|lock| lock.raw_data())
+        .ok_or(MessageError::InvalidWitnessArgs(first))
+}
+
+/// Blake160 hash of the compressed pubkey recovered from the 65 bytes `signature`.
+fn recover_pubkey_hash(
+    message: &[u8; 32],
+    signature: &[u8],
+) -> Result<[u8; BLAKE160_SIZE], SecpError> {
+    if signature[RECID_INDEX] > MAX_RECID {
+        return Err(SecpError::IllegalCallback);
+    }
+    let signature = Signature::from_slice(signature).map_err(|_| SecpError::ParseSignature)?;
+    signature
+        .to_recoverable()
+        .map_err(|_| SecpError::ParseSignature)?;
+    let pubkey = signature
+        .recover(&H256::from(*message))
+        .map_err(|_| SecpError::RecoverPubkey)?;
+    Ok(blake160(&pubkey.serialize()))
+}
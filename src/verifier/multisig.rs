@@ -0,0 +1,86 @@
+use super::{
+    first_witness_lock, group_input_indices, recover_pubkey_hash, SecpError, MAX_SCRIPT_SIZE,
+};
+use crate::{
+    multisig::{MultisigConfig, MultisigLockArgs, BLAKE160_SIZE, FLAGS_SIZE},
+    script_error::MultisigError,
+    sighash::{signing_message, MessageError, SIGNATURE_SIZE},
+    since::Since,
+};
+use ckb_types::{core::cell::ResolvedTransaction, packed::Script, prelude::*};
+use std::cmp;
+
+/// Verifies the inputs of `rtx` locked by `lock` like `c/secp256k1_blake160_multisig_all.c`.
+///
+/// `lock` is assumed to run the multisig lock, its code hash is not checked.
+pub fn verify_multisig_all(rtx: &ResolvedTransaction, lock: &Script) -> Result<(), MultisigError> {
+    if lock.as_slice().len() > MAX_SCRIPT_SIZE {
+        return Err(MultisigError::ScriptTooLong);
+    }
+    let args =
+        MultisigLockArgs::from_bytes(&lock.args().raw_data()).ok_or(MultisigError::ArgumentsLen)?;
+
+    let tx = &rtx.transaction;
+    let group_indices = group_input_indices(rtx, lock);
+    let lock_bytes = first_witness_lock(tx, &group_indices).map_err(message_error)?;
+    if lock_bytes.len() < FLAGS_SIZE {
+        return Err(MultisigError::WitnessSize);
+    }
+    // Checks the flags before the size of the whole lock, like the lock does.
+    let script_size = FLAGS_SIZE + BLAKE160_SIZE * usize::from(lock_bytes[3]);
+    let config = MultisigConfig::from_bytes(&lock_bytes[..cmp::min(script_size, lock_bytes.len())])
+        .map_err(|err| err.lock_error())?;
+    if lock_bytes.len() != config.lock_size() {
+        return Err(MultisigError::WitnessSize);
+    }
+    if config.hash160() != args.hash160 {
+        return Err(MultisigError::MultisigScriptHash);
+    }
+
+    let constraint = args.since_constraint();
+    for &index in &group_indices {
+        let since = Since::from_raw_value(tx.inputs().get_unchecked(index).since().unpack());
+        if since.flags() != constraint.flags() {
+            return Err(MultisigError::IncorrectSinceFlags);
+        }
+        if !since.satisfies(constraint) {
+            return Err(MultisigError::IncorrectSinceValue);
+        }
+    }
+
+    let message =
+        signing_message(tx, &group_indices, config.placeholder()).map_err(message_error)?;
+
+    // The lock sizes its bookkeeping by the threshold but indexes it by pubkey, this tracks
+    // every pubkey as intended.
+    let mut used = vec![false; config.pubkey_hashes.len()];
+    for signature in lock_bytes[script_size..].chunks_exact(SIGNATURE_SIZE) {
+        let pubkey_hash = recover_pubkey_hash(&message, signature).map_err(|err| match err {
+            SecpError::IllegalCallback => MultisigError::IllegalCallback,
+            SecpError::ParseSignature => MultisigError::SecpParseSignature,
+            SecpError::RecoverPubkey => MultisigError::SecpRecoverPubkey,
+        })?;
+        let position = config
+            .pubkey_hashes
+            .iter()
+            .zip(&used)
+            .position(|(hash, used)| !used && hash == &pubkey_hash)
+            .ok_or(MultisigError::Verification)?;
+        used[position] = true;
+    }
+    if used[..usize::from(config.require_first_n)]
+        .iter()
+        .any(|used| !used)
+    {
+        return Err(MultisigError::Verification);
+    }
+    Ok(())
+}
+
+fn message_error(err: MessageError) -> MultisigError {
+    match err {
+        MessageError::EmptyGroup | MessageError::MissingWitness(_) => MultisigError::Syscall,
+        MessageError::InvalidWitnessArgs(_) => MultisigError::Encoding,
+        MessageError::WitnessTooLarge(_) => MultisigError::WitnessSize,
+    }
+}
@@ -0,0 +1,49 @@
+use super::{
+    first_witness_lock, group_input_indices, recover_pubkey_hash, SecpError, MAX_SCRIPT_SIZE,
+};
+use crate::{
+    multisig::BLAKE160_SIZE,
+    script_error::SighashError,
+    sighash::{signing_message, MessageError, SIGNATURE_SIZE},
+};
+use ckb_types::{bytes::Bytes, core::cell::ResolvedTransaction, packed::Script, prelude::*};
+
+/// Verifies the inputs of `rtx` locked by `lock` like `c/secp256k1_blake160_sighash_all.c`.
+///
+/// `lock` is assumed to run the sighash lock, its code hash is not checked.
+pub fn verify_sighash_all(rtx: &ResolvedTransaction, lock: &Script) -> Result<(), SighashError> {
+    if lock.as_slice().len() > MAX_SCRIPT_SIZE {
+        return Err(SighashError::ScriptTooLong);
+    }
+    let args = lock.args().raw_data();
+    if args.len() != BLAKE160_SIZE {
+        return Err(SighashError::ArgumentsLen);
+    }
+
+    let tx = &rtx.transaction;
+    let group_indices = group_input_indices(rtx, lock);
+    let signature = first_witness_lock(tx, &group_indices).map_err(message_error)?;
+    if signature.len() != SIGNATURE_SIZE {
+        return Err(SighashError::ArgumentsLen);
+    }
+    let message = signing_message(tx, &group_indices, Bytes::from(vec![0; SIGNATURE_SIZE]))
+        .map_err(message_error)?;
+
+    let pubkey_hash = recover_pubkey_hash(&message, &signature).map_err(|err| match err {
+        SecpError::IllegalCallback => SighashError::IllegalCallback,
+        SecpError::ParseSignature => SighashError::SecpParseSignature,
+        SecpError::RecoverPubkey => SighashError::SecpRecoverPubkey,
+    })?;
+    if pubkey_hash[..] != args[..] {
+        return Err(SighashError::PubkeyBlake160Hash);
+    }
+    Ok(())
+}
+
+fn message_error(err: MessageError) -> SighashError {
+    match err {
+        MessageError::EmptyGroup | MessageError::MissingWitness(_) => SighashError::Syscall,
+        MessageError::InvalidWitnessArgs(_) => SighashError::Encoding,
+        MessageError::WitnessTooLarge(_) => SighashError::WitnessSize,
+    }
+}
@@ -0,0 +1,75 @@
+//! Registry of the released versions of the system scripts.
+//!
+//! Cells on chain may still reference binaries shipped by older releases. Every version listed
+//! in `specs/manifest.toml` is indexed here by its data hash, so the `code_hash` of a script
+//! referenced via `hash_type: data` or `data1` leads back to the release and, when the crate is
+//! built with the `history` feature, to the exact binary. Scripts referenced via
+//! `hash_type: type` have to be resolved to the data hash of the cell they point to first.
+
+use crate::{get_binary, SystemScript, BUNDLED_HISTORY, SCRIPT_VERSIONS};
+use ckb_types::bytes::Bytes;
+
+/// A released binary of a system script.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ScriptVersion {
+    /// Binary name of the script, as listed in `specs/manifest.toml`.
+    pub name: &'static str,
+    /// First release tag shipping the binary, e.g. `"v0.5.1"`.
+    pub tag: &'static str,
+    /// Blake2b hash of the binary.
+    pub data_hash: [u8; 32],
+    pub(crate) path: Option<&'static str>,
+}
+
+impl ScriptVersion {
+    /// The system script this is a version of.
+    pub fn script(&self) -> SystemScript {
+        SystemScript::from_name(self.name).expect("registered system script")
+    }
+
+    /// Whether this is the version bundled in `BUNDLED_CELL`.
+    pub fn is_current(&self) -> bool {
+        SystemScript::iter()
+            .any(|script| script.name() == self.name && script.data_hash() == self.data_hash)
+    }
+
+    /// Whether the binary is bundled in this crate, historical versions are only bundled with
+    /// the `history` feature.
+    pub fn is_bundled(&self) -> bool {
+        self.path.is_some()
+    }
+
+    /// Binary content of the version, `None` if it is not bundled.
+    pub fn binary(&self) -> Option<Bytes> {
+        let path = self.path?;
+        if self.is_current() {
+            return SystemScript::from_name(self.name).map(get_binary);
+        }
+        let data = BUNDLED_HISTORY
+            .get(path)
+            .expect("bundled historical binary");
+        Some(Bytes::from(data.into_owned()))
+    }
+}
+
+/// Iterates over all registered versions, each script's current version first.
+pub fn all_versions() -> impl Iterator<Item = &'static ScriptVersion> {
+    SCRIPT_VERSIONS.iter()
+}
+
+/// Finds the version whose binary hashes to `data_hash`.
+pub fn find_version(data_hash: &[u8; 32]) -> Option<&'static ScriptVersion> {
+    all_versions().find(|version| &version.data_hash == data_hash)
+}
+
+impl SystemScript {
+    /// Iterates over the registered versions of the script.
+    pub fn versions(self) -> impl Iterator<Item = &'static ScriptVersion> {
+        all_versions().filter(move |version| version.name == self.name())
+    }
+
+    /// The version bundled in `BUNDLED_CELL`.
+    pub fn current_version(self) -> &'static ScriptVersion {
+        find_version(&self.data_hash()).expect("current version is registered")
+    }
+}